@@ -0,0 +1,219 @@
+//! C4-PlantUML component diagram printer
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct C4Printer;
+
+impl Printer for C4Printer {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let is_tagged = |name: &str| {
+            opt.external
+                .iter()
+                .chain(&opt.databases)
+                .any(|tagged| tagged.replace('-', "_") == name)
+        };
+        let is_database = |name: &str| {
+            opt.databases
+                .iter()
+                .any(|tagged| tagged.replace('-', "_") == name)
+        };
+
+        // Workspace crates with their root modules (lib.rs / main.rs)
+        let mut crates: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+        for module in visitor.module_map.keys() {
+            if visitor.dependency_modules.contains(module) {
+                continue;
+            }
+            if let Some(prefix) = module
+                .strip_suffix("__lib")
+                .or_else(|| module.strip_suffix("__main"))
+            {
+                crates.entry(prefix).or_default().push(module);
+            }
+        }
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("!include <C4/C4_Component>\n");
+
+        // Components are the crate roots and their top level modules
+        let mut components: BTreeMap<&String, &str> = BTreeMap::new();
+        for (crate_name, roots) in &crates {
+            let description = roots
+                .iter()
+                .filter_map(|root| visitor.module_map.get(*root))
+                .map(|info| info.description.trim())
+                .find(|description| !description.is_empty())
+                .unwrap_or_default();
+
+            if is_tagged(crate_name) {
+                let kind = if is_database(crate_name) {
+                    "ContainerDb"
+                } else {
+                    "System_Ext"
+                };
+                uml_content.push_str(&format!(
+                    "{}({}, \"{}\", \"{}\")\n",
+                    kind,
+                    crate_name,
+                    crate_name,
+                    quote(description)
+                ));
+                for root in roots {
+                    components.insert(root, crate_name);
+                }
+                continue;
+            }
+
+            uml_content.push_str(&format!(
+                "Container_Boundary({}, \"{}\") {{\n",
+                crate_name, crate_name
+            ));
+            for root in roots {
+                let Some(info) = visitor.module_map.get(*root) else {
+                    continue;
+                };
+                let kind = if root.ends_with("__lib") {
+                    "lib"
+                } else {
+                    "main"
+                };
+                uml_content.push_str(&format!(
+                    "  Component({}, \"{}\", \"Crate root\", \"{}\")\n",
+                    root,
+                    kind,
+                    quote(&info.description)
+                ));
+                components.insert(root, root);
+
+                for submodule in &info.submodules {
+                    let Some(sub_info) = visitor.module_map.get(submodule) else {
+                        continue;
+                    };
+                    if components.insert(submodule, submodule).is_none() {
+                        let module_path = visitor.module_path(submodule);
+                        let name = module_path.rsplit("::").next().unwrap_or_default();
+                        uml_content.push_str(&format!(
+                            "  Component({}, \"{}\", \"Rust module\", \"{}\")\n",
+                            submodule,
+                            name,
+                            quote(&sub_info.description)
+                        ));
+                    }
+                }
+            }
+            uml_content.push_str("}\n");
+        }
+
+        // Tagged crates outside of the workspace
+        let external: BTreeSet<&String> = visitor
+            .crate_dependencies
+            .values()
+            .flatten()
+            .filter(|name| !crates.contains_key(name.as_str()) && is_tagged(name))
+            .collect();
+        for name in &external {
+            let kind = if is_database(name) {
+                "SystemDb_Ext"
+            } else {
+                "System_Ext"
+            };
+            uml_content.push_str(&format!("{}({}, \"{}\")\n", kind, name, name));
+        }
+
+        // `use` edges between the components
+        let component_of = |module: &str| -> Option<&str> {
+            let mut current = module.to_string();
+            for _ in 0..visitor.module_map.len() {
+                if let Some(component) = components.get(&current) {
+                    return Some(*component);
+                }
+                current = visitor.parent_module(&current)?.clone();
+            }
+            None
+        };
+        let mut relations: BTreeSet<(&str, &str, &str)> = BTreeSet::new();
+        for (module, info) in &visitor.module_map {
+            let Some(from) = component_of(module) else {
+                continue;
+            };
+            for used in &info.uses {
+                let target = visitor
+                    .resolve_module_path(module, used)
+                    .and_then(|target| component_of(&target))
+                    .or_else(|| {
+                        let crate_name = used.split("::").next()?;
+                        external
+                            .iter()
+                            .find(|name| name.as_str() == crate_name)
+                            .map(|name| name.as_str())
+                    });
+                if let Some(to) = target.filter(|to| *to != from) {
+                    relations.insert((from, to, "uses"));
+                }
+            }
+        }
+
+        // Crate dependencies without a `use` edge between their components
+        let crate_of = |component: &str| {
+            component
+                .strip_suffix("__lib")
+                .or_else(|| component.strip_suffix("__main"))
+                .map(str::to_string)
+                .or_else(|| {
+                    let root = visitor.root_module(component);
+                    root.strip_suffix("__lib")
+                        .or_else(|| root.strip_suffix("__main"))
+                        .map(str::to_string)
+                })
+                .unwrap_or_else(|| component.to_string())
+        };
+        let used_crates: BTreeSet<(String, String)> = relations
+            .iter()
+            .map(|(from, to, _)| (crate_of(from), crate_of(to)))
+            .collect();
+        for (crate_name, dependencies) in &visitor.crate_dependencies {
+            let Some((crate_name, _)) = crates.get_key_value(crate_name.as_str()) else {
+                continue;
+            };
+            for dependency in dependencies {
+                let is_drawn =
+                    crates.contains_key(dependency.as_str()) || external.contains(dependency);
+                if is_drawn && !used_crates.contains(&(crate_name.to_string(), dependency.clone()))
+                {
+                    relations.insert((crate_name, dependency, "depends on"));
+                }
+            }
+        }
+
+        for (from, to, label) in relations {
+            uml_content.push_str(&format!("Rel({}, {}, \"{}\")\n", from, to, label));
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print a C4 component diagram (C4-PlantUML)
+pub fn print_c4_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = C4Printer::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Text inside of a C4 macro argument
+fn quote(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('"', "'")
+}
@@ -0,0 +1,87 @@
+//! Plantuml call graph printer
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::call_graph::{CallGraph, CallNode};
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct CallGraphPrinter;
+
+impl Printer for CallGraphPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let graph = CallGraph::build(visitor, opt.expand_dependencies);
+        let starts = match &opt.function {
+            Some(function) => graph.find(visitor, function),
+            None => Vec::new(),
+        };
+        let graph = match &opt.function {
+            Some(_) => graph.reachable(&starts, opt.depth),
+            // Without a start only functions that call or are called are drawn
+            None => {
+                let mut connected = CallGraph::default();
+                for (from, to) in &graph.edges {
+                    connected.nodes.insert(from.clone());
+                    connected.nodes.insert(to.clone());
+                }
+                connected.edges = graph.edges;
+                connected
+            }
+        };
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("left to right direction\n");
+
+        let mut modules: BTreeMap<&String, Vec<&CallNode>> = BTreeMap::new();
+        for node in &graph.nodes {
+            modules.entry(&node.module).or_default().push(node);
+        }
+
+        let mut aliases = BTreeMap::new();
+        for (module, nodes) in modules {
+            uml_content.push_str(&format!(
+                "package \"{}\" {} {{\n",
+                visitor.module_path(module),
+                opt.module_color
+            ));
+            for node in nodes {
+                let alias = format!("f{}", aliases.len());
+                let color = if starts.contains(node) {
+                    format!(" {}", opt.trait_color)
+                } else {
+                    String::new()
+                };
+                uml_content.push_str(&format!(
+                    "  rectangle \"{}\" as {}{}\n",
+                    node.function, alias, color
+                ));
+                aliases.insert(node, alias);
+            }
+            uml_content.push_str("}\n");
+        }
+
+        for (from, to) in &graph.edges {
+            uml_content.push_str(&format!("{} --> {}\n", aliases[from], aliases[to]));
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the call graph (Plantuml), starting from `opt.function` if set
+pub fn print_call_graph_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let uml_content = CallGraphPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
@@ -0,0 +1,127 @@
+//! Plantuml graph of the conversions between types
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::conversions::{conversions, ConversionKind, ConversionType};
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct ConversionPrinter;
+
+impl Printer for ConversionPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let conversions = conversions(visitor, opt.expand_dependencies);
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("left to right direction\n");
+
+        // Model types grouped by module, other types on their own
+        let mut modules: BTreeMap<&String, BTreeSet<(&String, bool)>> = BTreeMap::new();
+        let mut external: BTreeSet<&String> = BTreeSet::new();
+        for conversion in &conversions {
+            for ty in [&conversion.from, &conversion.to] {
+                match ty {
+                    ConversionType::Model {
+                        module,
+                        name,
+                        is_enum,
+                    } => {
+                        modules.entry(module).or_default().insert((name, *is_enum));
+                    }
+                    ConversionType::External(name) => {
+                        external.insert(name);
+                    }
+                }
+            }
+        }
+
+        for (module, types) in modules {
+            uml_content.push_str(&format!(
+                "package \"{}\" as {} {} {{\n",
+                visitor.module_path(module),
+                module.replace('.', "_"),
+                opt.module_color
+            ));
+            for (name, is_enum) in types {
+                let kind = if is_enum { "enum" } else { "class" };
+                uml_content.push_str(&format!(
+                    "  {} \"{}\" as {}\n",
+                    kind,
+                    name,
+                    node_alias(&ConversionType::Model {
+                        module: module.clone(),
+                        name: name.clone(),
+                        is_enum,
+                    })
+                ));
+            }
+            uml_content.push_str("}\n");
+        }
+        for name in external {
+            uml_content.push_str(&format!(
+                "class \"{}\" as {} <<external>>\n",
+                name.replace('"', "'"),
+                node_alias(&ConversionType::External(name.clone()))
+            ));
+        }
+
+        for conversion in &conversions {
+            uml_content.push_str(&format!(
+                "{} {} {} : {}\n",
+                node_alias(&conversion.from),
+                arrow(conversion.kind),
+                node_alias(&conversion.to),
+                conversion.kind.label()
+            ));
+        }
+
+        uml_content.push_str("legend right\n");
+        uml_content.push_str("  solid: infallible (From, Into)\n");
+        uml_content.push_str("  <color:red>dashed</color>: fallible (TryFrom, TryInto)\n");
+        uml_content.push_str("  dotted: borrowed (AsRef)\n");
+        uml_content.push_str("endlegend\n");
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the conversions between types from their `From`, `TryFrom`, `Into` and `AsRef` impls (Plantuml)
+pub fn print_conversions_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let uml_content = ConversionPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Fallible conversions are dashed and red, borrowing ones dotted
+fn arrow(kind: ConversionKind) -> &'static str {
+    match kind {
+        _ if kind.is_fallible() => "-[#red,dashed]->",
+        ConversionKind::AsRef => "-[dotted]->",
+        _ => "-->",
+    }
+}
+
+pub(crate) fn node_alias(ty: &ConversionType) -> String {
+    match ty {
+        ConversionType::Model { module, name, .. } => {
+            format!("{}::{}", module.replace('.', "_"), name)
+        }
+        ConversionType::External(name) => {
+            let identifier: String = name
+                .chars()
+                .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                .collect();
+            format!("external__{}", identifier)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Plantuml diagram of the workspace crates and their dependencies
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::crates::DependencyKind;
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct CratePrinter;
+
+impl Printer for CratePrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let workspace = &visitor.workspace;
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("left to right direction\n");
+
+        uml_content.push_str("package \"workspace\" {\n");
+        for crate_info in &workspace.crates {
+            uml_content.push_str(&format!(
+                "  component \"{}\" as {} <<workspace>> {}\n",
+                label(&crate_info.name, crate_info.version.as_deref()),
+                workspace_alias(&crate_info.name),
+                opt.module_color
+            ));
+        }
+        uml_content.push_str("}\n");
+
+        let mut packages = BTreeMap::new();
+        let mut edges = Vec::new();
+        for crate_info in &workspace.crates {
+            for dependency in &crate_info.dependencies {
+                let to = if dependency.workspace {
+                    workspace_alias(&dependency.package)
+                } else {
+                    let alias = package_alias(&dependency.package, dependency.version.as_deref());
+                    packages.entry(alias.clone()).or_insert(dependency);
+                    alias
+                };
+
+                let mut labels = Vec::new();
+                let arrow = match dependency.kind {
+                    DependencyKind::Normal => "-->",
+                    DependencyKind::Dev => {
+                        labels.push("dev".to_string());
+                        "..>"
+                    }
+                    DependencyKind::Build => {
+                        labels.push("build".to_string());
+                        "-[dotted]->"
+                    }
+                };
+                if dependency.optional {
+                    labels.push(format!(
+                        "optional ({})",
+                        crate_info.enabling_features(&dependency.name).join(", ")
+                    ));
+                }
+                if dependency.name != dependency.package {
+                    labels.push(format!("as {}", dependency.name));
+                }
+
+                let mut edge = format!("{} {} {}", workspace_alias(&crate_info.name), arrow, to);
+                if !labels.is_empty() {
+                    edge.push_str(&format!(" : {}", labels.join("\\n")));
+                }
+                edges.push(edge);
+            }
+        }
+
+        // Packages locked in several versions are red
+        for (alias, dependency) in packages {
+            let color = if workspace.is_duplicate(&dependency.package) {
+                " #red"
+            } else {
+                ""
+            };
+            uml_content.push_str(&format!(
+                "component \"{}\" as {}{}\n",
+                label(&dependency.package, dependency.version.as_deref()),
+                alias,
+                color
+            ));
+        }
+
+        for edge in edges {
+            uml_content.push_str(&edge);
+            uml_content.push('\n');
+        }
+
+        let duplicates: Vec<String> = workspace
+            .locked_versions
+            .iter()
+            .filter(|(package, _)| workspace.is_duplicate(package))
+            .map(|(package, versions)| {
+                let versions: Vec<&str> = versions.iter().map(String::as_str).collect();
+                format!("{} {}", package, versions.join(", "))
+            })
+            .collect();
+        if !duplicates.is_empty() {
+            uml_content.push_str("legend right\n");
+            uml_content.push_str("  Duplicate versions in Cargo.lock\n");
+            for duplicate in duplicates {
+                uml_content.push_str(&format!("  {}\n", duplicate));
+            }
+            uml_content.push_str("endlegend\n");
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the workspace crates with their dependencies (Plantuml)
+pub fn print_crates_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = CratePrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn label(name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{}\\n{}", name, version),
+        None => name.to_string(),
+    }
+}
+
+fn workspace_alias(name: &str) -> String {
+    format!("workspace__{}", identifier(name))
+}
+
+/// Every locked version of a package is a component of its own
+fn package_alias(name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{}__{}", identifier(name), identifier(version)),
+        None => identifier(name),
+    }
+}
+
+fn identifier(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,337 @@
+//! D2 printer of the overview
+//!
+//! Modules become nested containers, structs, enums and traits `shape: class`
+//! objects. Connections refer to the objects by their container path, e.g.
+//! `app__lib.app__model.Node`.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::{
+    compact_tokens, FunctionInfo, ModuleInfo, ModulesVisitor,
+};
+use cargo_diagram_visitors::relations::{
+    field_relations, signature_dependencies, Ownership, Relation, SignatureDependency,
+};
+
+use crate::mermaid::css_color;
+use crate::uml::{trait_edges, TraitTarget};
+use crate::{PrintOptions, Printer};
+
+struct D2Printer;
+
+impl Printer for D2Printer {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let mut content = String::from("direction: right\n");
+
+        // Container path of every drawn module
+        let mut paths = BTreeMap::new();
+        for (module, info) in &visitor.module_map {
+            if module.ends_with("__lib") || module.ends_with("__main") {
+                if visitor.dependency_modules.contains(module) && !opt.expand_dependencies {
+                    // Collapsed dependency crate
+                    let name = module
+                        .rsplit_once("__")
+                        .map_or(module.as_str(), |(name, _)| name);
+                    content.push_str(&format!(
+                        "{}: {} {{\n  shape: package\n  style.fill: {}\n}}\n",
+                        module.replace('.', "_"),
+                        quote(name),
+                        quote(&css_color(&opt.module_color))
+                    ));
+                    continue;
+                }
+                add_container(&mut content, &mut paths, "", module, info, visitor, &opt);
+            }
+        }
+        let object = |module: &str, name: &str| match paths.get(module) {
+            Some(path) => format!("{}.{}", path, name),
+            None => format!("{}.{}", module.replace('.', "_"), name),
+        };
+
+        add_trait_relations(&mut content, visitor, &opt, &object);
+
+        if opt.relations {
+            for relation in field_relations(visitor, opt.expand_dependencies) {
+                let from = object(&relation.from_module, &relation.from_type);
+                let to = object(&relation.to_module, &relation.to_type);
+                content.push_str(&relation_edge(&relation, &from, &to));
+            }
+        }
+
+        if opt.signature_edges {
+            let uses =
+                signature_dependencies(visitor, opt.expand_dependencies, opt.functions_private);
+            for dependency in uses {
+                let from = object(
+                    &dependency.from_module,
+                    dependency.from_type.as_deref().unwrap_or("mod"),
+                );
+                let to = object(&dependency.to_module, &dependency.to_type);
+                content.push_str(&signature_edge(&dependency, &from, &to));
+            }
+        }
+
+        content
+    }
+}
+
+/// Print the overview as D2 diagram
+pub fn print_d2_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let content = D2Printer::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Container of a module with its items and the containers of its submodules
+fn add_container(
+    content: &mut String,
+    paths: &mut BTreeMap<String, String>,
+    parent: &str,
+    module: &str,
+    info: &ModuleInfo,
+    visitor: &ModulesVisitor,
+    opt: &PrintOptions,
+) {
+    let key = module.replace('.', "_");
+    let (path, level) = if parent.is_empty() {
+        (key.clone(), 0)
+    } else {
+        (format!("{}.{}", parent, key), parent.split('.').count())
+    };
+    paths.insert(module.to_string(), path.clone());
+
+    let indent = "  ".repeat(level);
+    content.push_str(&format!(
+        "{}{}: {} {{\n",
+        indent,
+        key,
+        quote(&visitor.module_path(module))
+    ));
+    content.push_str(&format!(
+        "{}  style.fill: {}\n",
+        indent,
+        quote(&css_color(&opt.module_color))
+    ));
+
+    for (struct_name, struct_info) in &info.structs {
+        let fields = struct_info
+            .members
+            .iter()
+            .map(|member| (member.name.clone(), compact_tokens(&member.member_type)))
+            .collect::<Vec<_>>();
+        content.push_str(&class(
+            &indent,
+            struct_name,
+            struct_name,
+            None,
+            &fields,
+            &struct_info.functions,
+            opt,
+        ));
+    }
+
+    for (enum_name, enum_info) in &info.enums {
+        let variants = enum_info
+            .variants
+            .iter()
+            .map(|variant| (variant.clone(), String::new()))
+            .collect::<Vec<_>>();
+        content.push_str(&class(
+            &indent,
+            enum_name,
+            &format!("«enum» {}", enum_name),
+            None,
+            &variants,
+            &enum_info.functions,
+            opt,
+        ));
+    }
+
+    for trait_info in &info.traits {
+        content.push_str(&class(
+            &indent,
+            &trait_info.name,
+            &format!("«trait» {}", trait_info.name),
+            Some(&opt.trait_color),
+            &[],
+            &trait_info.functions,
+            opt,
+        ));
+    }
+
+    if info
+        .functions
+        .iter()
+        .any(|function| function.public || opt.functions_private)
+    {
+        content.push_str(&class(
+            &indent,
+            "mod",
+            &format!("«mod» {}", visitor.module_path(module)),
+            None,
+            &[],
+            &info.functions,
+            opt,
+        ));
+    }
+
+    for submodule in &info.submodules {
+        if let Some(sub_info) = visitor.module_map.get(submodule) {
+            add_container(content, paths, &path, submodule, sub_info, visitor, opt);
+        }
+    }
+
+    content.push_str(&format!("{}}}\n", indent));
+}
+
+/// `shape: class` object, fields are `name: type` and methods `+name(params): return`
+fn class(
+    indent: &str,
+    key: &str,
+    label: &str,
+    color: Option<&String>,
+    fields: &[(String, String)],
+    functions: &[FunctionInfo],
+    opt: &PrintOptions,
+) -> String {
+    let mut content = format!("{}  {}: {} {{\n", indent, key, quote(label));
+    content.push_str(&format!("{}    shape: class\n", indent));
+    if let Some(color) = color {
+        content.push_str(&format!(
+            "{}    style.fill: {}\n",
+            indent,
+            quote(&css_color(color))
+        ));
+    }
+    for (name, field_type) in fields {
+        if field_type.is_empty() {
+            content.push_str(&format!("{}    {}\n", indent, quote(name)));
+        } else {
+            content.push_str(&format!(
+                "{}    {}: {}\n",
+                indent,
+                quote(name),
+                quote(field_type)
+            ));
+        }
+    }
+    for function in functions {
+        if !opt.functions_private && !function.public {
+            continue;
+        }
+        let visibility = if function.public { "+" } else { "-" };
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "{}: {}",
+                    parameter.name,
+                    compact_tokens(&parameter.param_type)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let method = format!("{}{}({})", visibility, function.name, parameters);
+        match function.return_type.as_str() {
+            "()" => content.push_str(&format!("{}    {}\n", indent, quote(&method))),
+            return_type => content.push_str(&format!(
+                "{}    {}: {}\n",
+                indent,
+                quote(&method),
+                quote(&compact_tokens(return_type))
+            )),
+        }
+    }
+    content.push_str(&format!("{}  }}\n", indent));
+    content
+}
+
+/// Realization (dashed, hollow triangle) and supertrait (solid, hollow triangle) connections
+fn add_trait_relations(
+    content: &mut String,
+    visitor: &ModulesVisitor,
+    opt: &PrintOptions,
+    object: &dyn Fn(&str, &str) -> String,
+) {
+    let mut stubs = BTreeMap::new();
+    let mut lines = Vec::new();
+    for edge in trait_edges(visitor, opt) {
+        let target = match &edge.target {
+            TraitTarget::Drawn(module, name) => object(module, name),
+            TraitTarget::Stub(path) => {
+                let key = format!("external_traits__{}", path.replace("::", "_"));
+                stubs.insert(key.clone(), path.clone());
+                key
+            }
+        };
+        let dash = if edge.supertrait {
+            ""
+        } else {
+            "  style.stroke-dash: 3\n"
+        };
+        lines.push(format!(
+            "{} -> {}: {{\n{}  target-arrowhead.shape: triangle\n  target-arrowhead.style.filled: false\n}}\n",
+            object(&edge.from_module, &edge.from_type),
+            target,
+            dash
+        ));
+    }
+
+    for (key, path) in stubs {
+        content.push_str(&format!(
+            "{}: {} {{\n  style.fill: {}\n  style.stroke-dash: 3\n}}\n",
+            key,
+            quote(&format!("«external» {}", path)),
+            quote(&css_color(&opt.trait_color))
+        ));
+    }
+    for line in lines {
+        content.push_str(&line);
+    }
+}
+
+/// Field relation: filled diamond owned, hollow diamond shared and arrow referenced
+///
+/// The diamond sits at the owner, so owned and shared fields point backwards.
+fn relation_edge(relation: &Relation, from: &str, to: &str) -> String {
+    let mut label = relation.field.clone();
+    if let Some(dispatch) = relation.dispatch {
+        label.push_str(&format!(" «{}»", dispatch.label()));
+    }
+    if !relation.multiplicity.label().is_empty() {
+        label.push_str(&format!(" [{}]", relation.multiplicity.label()));
+    }
+    let filled = match relation.ownership {
+        Ownership::Composition => true,
+        Ownership::Aggregation => false,
+        Ownership::Association => return format!("{} -> {}: {}\n", from, to, quote(&label)),
+    };
+    format!(
+        "{} <- {}: {} {{\n  source-arrowhead.shape: diamond\n  source-arrowhead.style.filled: {}\n}}\n",
+        from,
+        to,
+        quote(&label),
+        filled
+    )
+}
+
+fn signature_edge(dependency: &SignatureDependency, from: &str, to: &str) -> String {
+    let label = match dependency.dispatch {
+        Some(dispatch) => format!(" {}", quote(&format!("«{}»", dispatch.label()))),
+        None => String::new(),
+    };
+    format!(
+        "{} -> {}:{} {{\n  style.stroke-dash: 3\n}}\n",
+        from, to, label
+    )
+}
+
+/// Double quoted D2 string
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,71 @@
+//! Markdown report of the traits used as `dyn Trait` or `impl Trait`
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::relations::{dispatch_sites, Dispatch, DispatchSite};
+
+use crate::{PrintOptions, Printer};
+
+struct DispatchReportPrinter;
+
+impl Printer for DispatchReportPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let mut report = String::from("# Dispatch\n");
+
+        let sites = dispatch_sites(visitor, opt.expand_dependencies);
+        if sites.is_empty() {
+            report.push_str("\nNo `dyn Trait` or `impl Trait` found.\n");
+        }
+
+        for (trait_path, trait_sites) in sites {
+            let count = |dispatch| {
+                trait_sites
+                    .iter()
+                    .filter(|site| site.dispatch == dispatch)
+                    .count()
+            };
+            report.push_str(&format!(
+                "\n## `{}`\n\n{} dynamic, {} static\n\n",
+                trait_path,
+                count(Dispatch::Dyn),
+                count(Dispatch::Impl)
+            ));
+            report.push_str("| Module | Item | Position | Dispatch |\n| --- | --- | --- | --- |\n");
+            for site in &trait_sites {
+                report.push_str(&format!(
+                    "| {} | `{}` | {} | `{}` |\n",
+                    site.module,
+                    site.item,
+                    position_label(site),
+                    site.dispatch.label()
+                ));
+            }
+        }
+
+        report
+    }
+}
+
+/// Print a markdown report of the `dyn Trait` and `impl Trait` sites, grouped by trait
+pub fn print_dispatch_report_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let report = DispatchReportPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(report.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Position with the name of the field or parameter, e.g. "parameter `handler`"
+fn position_label(site: &DispatchSite) -> String {
+    if site.name.is_empty() {
+        site.position.label().to_string()
+    } else {
+        format!("{} `{}`", site.position.label(), site.name)
+    }
+}
@@ -0,0 +1,315 @@
+//! Graphviz DOT printer of the overview
+//!
+//! Modules become nested `cluster_*` subgraphs and items HTML-like table nodes with
+//! a row for the fields and one for the methods. Render with `dot -Tsvg`.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::{
+    compact_tokens, FunctionInfo, ModuleInfo, ModulesVisitor,
+};
+use cargo_diagram_visitors::relations::{
+    field_relations, signature_dependencies, Ownership, Relation, SignatureDependency,
+};
+
+use crate::mermaid::css_color;
+use crate::uml::{format_function_signature, trait_edges, TraitTarget};
+use crate::{PrintOptions, Printer};
+
+struct DotPrinter;
+
+impl Printer for DotPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let mut content = String::from("digraph overview {\n");
+        content.push_str("  rankdir=LR\n");
+        content.push_str("  compound=true\n");
+        content.push_str("  fontname=\"Helvetica\"\n");
+        content.push_str("  node [shape=plain fontname=\"Helvetica\"]\n");
+        content.push_str("  edge [fontname=\"Helvetica\" fontsize=10]\n");
+
+        for (module, info) in &visitor.module_map {
+            if module.ends_with("__lib") || module.ends_with("__main") {
+                if visitor.dependency_modules.contains(module) && !opt.expand_dependencies {
+                    // Collapsed dependency crate
+                    let name = module
+                        .rsplit_once("__")
+                        .map_or(module.as_str(), |(name, _)| name);
+                    content.push_str(&format!(
+                        "  {} [shape=tab style=filled fillcolor=\"{}\" label=\"{}\"]\n",
+                        node_id(module, "crate"),
+                        css_color(&opt.module_color),
+                        name
+                    ));
+                    continue;
+                }
+                add_cluster(&mut content, module, info, visitor, 1, &opt);
+            }
+        }
+
+        add_trait_relations(&mut content, visitor, &opt);
+
+        if opt.relations {
+            for relation in field_relations(visitor, opt.expand_dependencies) {
+                content.push_str(&relation_edge(&relation));
+            }
+        }
+
+        if opt.signature_edges {
+            let uses =
+                signature_dependencies(visitor, opt.expand_dependencies, opt.functions_private);
+            for dependency in uses {
+                content.push_str(&signature_edge(&dependency));
+            }
+        }
+
+        content.push_str("}\n");
+        content
+    }
+}
+
+/// Print the overview as Graphviz DOT graph
+pub fn print_dot_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let content = DotPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Cluster of a module with its items and the clusters of its submodules
+fn add_cluster(
+    content: &mut String,
+    module: &str,
+    info: &ModuleInfo,
+    visitor: &ModulesVisitor,
+    level: usize,
+    opt: &PrintOptions,
+) {
+    let indent = "  ".repeat(level);
+    content.push_str(&format!(
+        "{}subgraph \"cluster_{}\" {{\n",
+        indent,
+        module.replace('.', "_")
+    ));
+    content.push_str(&format!(
+        "{}  label=\"{}\"\n",
+        indent,
+        visitor.module_path(module)
+    ));
+    content.push_str(&format!(
+        "{}  style=filled\n{}  fillcolor=\"{}\"\n",
+        indent,
+        indent,
+        css_color(&opt.module_color)
+    ));
+
+    for (struct_name, struct_info) in &info.structs {
+        let fields = struct_info
+            .members
+            .iter()
+            .map(|member| format!("{}: {}", member.name, compact_tokens(&member.member_type)))
+            .collect::<Vec<_>>();
+        let label = table(
+            "struct",
+            struct_name,
+            "white",
+            &fields,
+            &functions(&struct_info.functions, opt),
+        );
+        content.push_str(&node(&indent, module, struct_name, &label));
+    }
+
+    for (enum_name, enum_info) in &info.enums {
+        let label = table(
+            "enum",
+            enum_name,
+            "white",
+            &enum_info.variants,
+            &functions(&enum_info.functions, opt),
+        );
+        content.push_str(&node(&indent, module, enum_name, &label));
+    }
+
+    for trait_info in &info.traits {
+        let label = table(
+            "trait",
+            &trait_info.name,
+            &css_color(&opt.trait_color),
+            &[],
+            &functions(&trait_info.functions, opt),
+        );
+        content.push_str(&node(&indent, module, &trait_info.name, &label));
+    }
+
+    let module_functions = functions(&info.functions, opt);
+    if !module_functions.is_empty() {
+        let label = table(
+            "mod",
+            &visitor.module_path(module),
+            "white",
+            &[],
+            &module_functions,
+        );
+        content.push_str(&node(&indent, module, "mod", &label));
+    }
+
+    for submodule in &info.submodules {
+        if let Some(sub_info) = visitor.module_map.get(submodule) {
+            add_cluster(content, submodule, sub_info, visitor, level + 1, opt);
+        }
+    }
+
+    content.push_str(&format!("{}}}\n", indent));
+}
+
+fn node(indent: &str, module: &str, name: &str, label: &str) -> String {
+    format!(
+        "{}  {} [label=<{}>]\n",
+        indent,
+        node_id(module, name),
+        label
+    )
+}
+
+/// Signatures with `+` for public and `-` for private visibility
+fn functions(functions: &[FunctionInfo], opt: &PrintOptions) -> Vec<String> {
+    functions
+        .iter()
+        .filter(|function| opt.functions_private || function.public)
+        .map(|function| {
+            let visibility = if function.public { "+" } else { "-" };
+            let signature = compact_tokens(&format_function_signature(function));
+            format!("{} {}", visibility, signature)
+        })
+        .collect()
+}
+
+/// HTML-like label: title with stereotype, a row of fields and a row of methods
+fn table(
+    stereotype: &str,
+    name: &str,
+    color: &str,
+    fields: &[String],
+    methods: &[String],
+) -> String {
+    let mut label = format!(
+        "<TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\" BGCOLOR=\"{}\">",
+        color
+    );
+    label.push_str(&format!(
+        "<TR><TD><I>«{}»</I><BR/><B>{}</B></TD></TR>",
+        stereotype,
+        escape_html(name)
+    ));
+    for rows in [fields, methods] {
+        let lines = rows
+            .iter()
+            .map(|row| format!("{}<BR/>", escape_html(row)))
+            .collect::<String>();
+        if lines.is_empty() {
+            label.push_str("<TR><TD> </TD></TR>");
+        } else {
+            label.push_str(&format!(
+                "<TR><TD ALIGN=\"LEFT\" BALIGN=\"LEFT\">{}</TD></TR>",
+                lines
+            ));
+        }
+    }
+    label.push_str("</TABLE>");
+    label
+}
+
+/// Realization (dashed, hollow arrow) and supertrait (solid, hollow arrow) edges
+fn add_trait_relations(content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    let mut stubs = BTreeMap::new();
+    let mut lines = Vec::new();
+    for edge in trait_edges(visitor, opt) {
+        let target = match &edge.target {
+            TraitTarget::Drawn(module, name) => node_id(module, name),
+            TraitTarget::Stub(path) => {
+                let id = node_id("external_traits", &path.replace("::", "_"));
+                stubs.insert(id.clone(), path.clone());
+                id
+            }
+        };
+        let style = if edge.supertrait {
+            "arrowhead=empty"
+        } else {
+            "style=dashed arrowhead=empty"
+        };
+        let from = node_id(&edge.from_module, &edge.from_type);
+        lines.push(format!("  {} -> {} [{}]\n", from, target, style));
+    }
+
+    for (id, path) in stubs {
+        content.push_str(&format!(
+            "  {} [shape=box style=\"rounded,filled,dashed\" fillcolor=\"{}\" label=\"{}\"]\n",
+            id,
+            css_color(&opt.trait_color),
+            escape_quotes(&path)
+        ));
+    }
+    for line in lines {
+        content.push_str(&line);
+    }
+}
+
+/// Field relation: filled diamond owned, hollow diamond shared and open arrow referenced
+fn relation_edge(relation: &Relation) -> String {
+    let style = match relation.ownership {
+        Ownership::Composition => "dir=both arrowtail=diamond arrowhead=none",
+        Ownership::Aggregation => "dir=both arrowtail=odiamond arrowhead=none",
+        Ownership::Association => "arrowhead=vee",
+    };
+    let label = match relation.dispatch {
+        Some(dispatch) => format!("{} «{}»", relation.field, dispatch.label()),
+        None => relation.field.clone(),
+    };
+    let multiplicity = match relation.multiplicity.label() {
+        "" => String::new(),
+        label => format!(" headlabel=\"{}\"", label),
+    };
+    format!(
+        "  {} -> {} [{} label=\"{}\"{}]\n",
+        node_id(&relation.from_module, &relation.from_type),
+        node_id(&relation.to_module, &relation.to_type),
+        style,
+        escape_quotes(&label),
+        multiplicity
+    )
+}
+
+fn signature_edge(dependency: &SignatureDependency) -> String {
+    let from = node_id(
+        &dependency.from_module,
+        dependency.from_type.as_deref().unwrap_or("mod"),
+    );
+    let to = node_id(&dependency.to_module, &dependency.to_type);
+    match dependency.dispatch {
+        Some(dispatch) => format!(
+            "  {} -> {} [style=dashed arrowhead=vee label=\"«{}»\"]\n",
+            from,
+            to,
+            dispatch.label()
+        ),
+        None => format!("  {} -> {} [style=dashed arrowhead=vee]\n", from, to),
+    }
+}
+
+/// Quoted node identifier of a type, `mod` for the functions of the module
+fn node_id(module: &str, name: &str) -> String {
+    format!("\"{}__{}\"", module.replace('.', "_"), name)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_quotes(text: &str) -> String {
+    text.replace('"', "\\\"")
+}
@@ -0,0 +1,129 @@
+//! Plantuml diagram of the error types with their messages, sources and conversions
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::conversions::ConversionType;
+use cargo_diagram_visitors::errors::{ErrorEdgeKind, ErrorHierarchy, ErrorType};
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::conversions::node_alias;
+use crate::{PrintOptions, Printer};
+
+struct ErrorPrinter;
+
+impl Printer for ErrorPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let hierarchy = ErrorHierarchy::build(visitor, opt.expand_dependencies);
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("left to right direction\n");
+
+        let mut modules: BTreeMap<&String, Vec<&ErrorType>> = BTreeMap::new();
+        for error in &hierarchy.errors {
+            modules.entry(&error.module).or_default().push(error);
+        }
+        for (module, errors) in modules {
+            uml_content.push_str(&format!(
+                "package \"{}\" as {} {} {{\n",
+                visitor.module_path(module),
+                module.replace('.', "_"),
+                opt.module_color
+            ));
+            for error in errors {
+                uml_content.push_str(&print_error(error));
+            }
+            uml_content.push_str("}\n");
+        }
+
+        let external: BTreeSet<&String> = hierarchy
+            .edges
+            .iter()
+            .flat_map(|edge| [&edge.source, &edge.target])
+            .filter_map(|node| match node {
+                ConversionType::External(name) => Some(name),
+                ConversionType::Model { .. } => None,
+            })
+            .collect();
+        for name in external {
+            uml_content.push_str(&format!(
+                "class \"{}\" as {} <<external>>\n",
+                name.replace('"', "'"),
+                node_alias(&ConversionType::External(name.clone()))
+            ));
+        }
+
+        for edge in &hierarchy.edges {
+            let source = node_alias(&edge.source);
+            let target = node_alias(&edge.target);
+            let line = match &edge.kind {
+                ErrorEdgeKind::Wraps {
+                    variant,
+                    field,
+                    from,
+                } => {
+                    let attribute = if *from { "#[from]" } else { "#[source]" };
+                    // Structs have a single variant named after themselves
+                    let label = match &edge.target {
+                        ConversionType::Model { is_enum: true, .. } => variant,
+                        _ => field,
+                    };
+                    format!("{} *-- {} : {} {}", target, source, label, attribute)
+                }
+                ErrorEdgeKind::From => format!("{} ..> {} : From", source, target),
+            };
+            uml_content.push_str(&line);
+            uml_content.push('\n');
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the error types with the errors they wrap and convert from (Plantuml)
+pub fn print_errors_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = ErrorPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Class of an error with a line per variant and its message
+fn print_error(error: &ErrorType) -> String {
+    let (kind, stereotype) = match (error.is_enum, error.derived) {
+        (true, true) => ("enum", "<<thiserror>>"),
+        (true, false) => ("enum", "<<error>>"),
+        (false, true) => ("class", "<<thiserror>>"),
+        (false, false) => ("class", "<<error>>"),
+    };
+    let alias = node_alias(&ConversionType::Model {
+        module: error.module.clone(),
+        name: error.name.clone(),
+        is_enum: error.is_enum,
+    });
+
+    let mut uml_content = format!(
+        "  {} \"{}\" as {} {} {{\n",
+        kind, error.name, alias, stereotype
+    );
+    for variant in &error.variants {
+        // `#[error(transparent)]` forwards the message of the source
+        let message = match variant.message.as_str() {
+            "" => String::new(),
+            "transparent" => "#[error(transparent)]".to_string(),
+            message => format!("\"{}\"", message.replace('"', "'")),
+        };
+        match (error.is_enum, message.is_empty()) {
+            (true, true) => uml_content.push_str(&format!("    {}\n", variant.name)),
+            (true, false) => uml_content.push_str(&format!("    {} : {}\n", variant.name, message)),
+            (false, true) => {}
+            (false, false) => uml_content.push_str(&format!("    {}\n", message)),
+        }
+    }
+    uml_content.push_str("  }\n");
+    uml_content
+}
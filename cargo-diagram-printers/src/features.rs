@@ -0,0 +1,149 @@
+//! Plantuml graph of the cargo features of the workspace crates
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::features::{CrateFeatures, FeatureGraph, FeatureTarget};
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct FeaturePrinter;
+
+impl Printer for FeaturePrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let graph = FeatureGraph::build(visitor);
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("left to right direction\n");
+
+        for crate_features in graph.crates.iter().filter(|crate_features| {
+            !crate_features.features.is_empty() || !crate_features.gated_items.is_empty()
+        }) {
+            uml_content.push_str(&print_crate(crate_features, &opt));
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the features of the workspace crates with what they enable (Plantuml)
+pub fn print_features_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = FeaturePrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn print_crate(crate_features: &CrateFeatures, opt: &PrintOptions) -> String {
+    let prefix = identifier(&crate_features.name);
+    let feature_alias = |feature: &str| format!("{}__feature__{}", prefix, identifier(feature));
+    let dependency_alias =
+        |dependency: &str| format!("{}__dep__{}", prefix, identifier(dependency));
+
+    // Features enabled but not declared are drawn as well, cargo rejects them
+    let mut features: BTreeSet<&String> = crate_features.features.iter().collect();
+    features.extend(
+        crate_features
+            .edges
+            .iter()
+            .filter_map(|(_, target)| match target {
+                FeatureTarget::Feature(feature) => Some(feature),
+                _ => None,
+            }),
+    );
+    if opt.feature_items {
+        features.extend(crate_features.gated_items.keys());
+    }
+
+    let mut uml_content = format!(
+        "package \"{}\" <<crate>> {} {{\n",
+        crate_features.name, opt.module_color
+    );
+    for feature in features {
+        let stereotype = if crate_features.implicit.contains(feature) {
+            " <<implicit>>"
+        } else if !crate_features.features.contains(feature) {
+            " <<undeclared>>"
+        } else {
+            ""
+        };
+        uml_content.push_str(&format!(
+            "  usecase \"{}\" as {}{}\n",
+            feature,
+            feature_alias(feature),
+            stereotype
+        ));
+    }
+    for dependency in &crate_features.dependencies {
+        let stereotype = if crate_features.optional.contains(dependency) {
+            "<<optional>>"
+        } else {
+            "<<dependency>>"
+        };
+        uml_content.push_str(&format!(
+            "  component \"{}\" as {} {} {}\n",
+            dependency,
+            dependency_alias(dependency),
+            stereotype,
+            opt.trait_color
+        ));
+    }
+    uml_content.push_str("}\n");
+
+    // Weak dependency features don't enable the dependency and are dashed
+    for (feature, target) in &crate_features.edges {
+        let from = feature_alias(feature);
+        let edge = match target {
+            FeatureTarget::Feature(enabled) => {
+                format!("{} --> {} : enables", from, feature_alias(enabled))
+            }
+            FeatureTarget::Dependency(dependency) => {
+                format!("{} --> {} : enables", from, dependency_alias(dependency))
+            }
+            FeatureTarget::DependencyFeature {
+                dependency,
+                feature,
+                weak: false,
+            } => format!(
+                "{} --> {} : enables {}",
+                from,
+                dependency_alias(dependency),
+                feature
+            ),
+            FeatureTarget::DependencyFeature {
+                dependency,
+                feature,
+                weak: true,
+            } => format!(
+                "{} ..> {} : enables {} (weak)",
+                from,
+                dependency_alias(dependency),
+                feature
+            ),
+        };
+        uml_content.push_str(&edge);
+        uml_content.push('\n');
+    }
+
+    if opt.feature_items {
+        for (feature, items) in &crate_features.gated_items {
+            uml_content.push_str(&format!("note right of {}\n", feature_alias(feature)));
+            for item in items {
+                uml_content.push_str(&format!("  {}\n", item));
+            }
+            uml_content.push_str("end note\n");
+        }
+    }
+    uml_content
+}
+
+fn identifier(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,120 @@
+//! Plantuml activity diagram of a function's control flow
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use cargo_diagram_visitors::call_graph::CallGraph;
+use cargo_diagram_visitors::flow::{function_flow, FlowNode};
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct FlowPrinter;
+
+impl Printer for FlowPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+
+        let function = opt.function.as_deref().unwrap_or_default();
+        let graph = CallGraph::build(visitor, opt.expand_dependencies);
+        let flow = graph.find(visitor, function).into_iter().find_map(|node| {
+            let source_file = &visitor.module_map.get(&node.module)?.source_file;
+            let flow = function_flow(Path::new(source_file), &node.function)?;
+            Some((node.path(visitor), flow))
+        });
+
+        match flow {
+            Some((path, flow)) => {
+                uml_content.push_str(&format!("title {}\n", path));
+                uml_content.push_str("start\n");
+                add_flow_nodes(&mut uml_content, &flow, 0);
+                uml_content.push_str("stop\n");
+            }
+            None => uml_content.push_str(&format!("title {} (not found)\n", function)),
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the control flow of `opt.function` as activity diagram (Plantuml)
+pub fn print_flow_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = FlowPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn add_flow_nodes(uml_content: &mut String, nodes: &[FlowNode], level: usize) {
+    let indent = "  ".repeat(level);
+
+    for node in nodes {
+        match node {
+            FlowNode::Statements(statements) => {
+                uml_content.push_str(&format!("{}:{};\n", indent, statements.join("\n")));
+            }
+            FlowNode::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                uml_content.push_str(&format!("{}if ({}) then (yes)\n", indent, condition));
+                add_flow_nodes(uml_content, then_branch, level + 1);
+
+                // Flatten `else if` chains
+                let mut else_branch = else_branch.as_deref();
+                while let Some(branch) = else_branch {
+                    match branch {
+                        [FlowNode::If {
+                            condition,
+                            then_branch,
+                            else_branch: next,
+                        }] => {
+                            uml_content.push_str(&format!(
+                                "{}elseif ({}) then (yes)\n",
+                                indent, condition
+                            ));
+                            add_flow_nodes(uml_content, then_branch, level + 1);
+                            else_branch = next.as_deref();
+                        }
+                        _ => {
+                            uml_content.push_str(&format!("{}else (no)\n", indent));
+                            add_flow_nodes(uml_content, branch, level + 1);
+                            else_branch = None;
+                        }
+                    }
+                }
+                uml_content.push_str(&format!("{}endif\n", indent));
+            }
+            FlowNode::Match { expression, arms } => {
+                uml_content.push_str(&format!("{}switch (match {})\n", indent, expression));
+                for (pattern, body) in arms {
+                    uml_content.push_str(&format!("{}case ({})\n", indent, pattern));
+                    add_flow_nodes(uml_content, body, level + 1);
+                }
+                uml_content.push_str(&format!("{}endswitch\n", indent));
+            }
+            FlowNode::Loop { header, body } => {
+                uml_content.push_str(&format!("{}while ({})\n", indent, header));
+                add_flow_nodes(uml_content, body, level + 1);
+                uml_content.push_str(&format!("{}endwhile\n", indent));
+            }
+            FlowNode::Try(expression) => {
+                uml_content.push_str(&format!(
+                    "{}if ({} is Err / None) then (yes)\n{}  :return early (?);\n{}  stop\n{}endif\n",
+                    indent, expression, indent, indent, indent
+                ));
+            }
+            FlowNode::Return(value) => {
+                uml_content.push_str(&format!("{}:return {};\n{}stop\n", indent, value, indent));
+            }
+            FlowNode::Break => uml_content.push_str(&format!("{}break\n", indent)),
+            FlowNode::Continue => {
+                uml_content.push_str(&format!("{}:continue;\n{}detach\n", indent, indent))
+            }
+        }
+    }
+}
@@ -1,17 +1,76 @@
 //! Printers for cargo-diagram-visitors
 
 use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+pub mod c4;
+pub mod call_graph;
 pub mod console;
+pub mod conversions;
+pub mod crates;
+pub mod d2;
+pub mod dispatch;
+pub mod dot;
+pub mod errors;
+pub mod features;
+pub mod flow;
+pub mod mermaid;
+pub mod module_graph;
+pub mod proc_macros;
+pub mod sequence;
+pub mod state_machine;
+pub mod structs;
+pub mod trait_matrix;
 pub mod uml;
 
 trait Printer {
     fn print(visitor: &ModulesVisitor, options: PrintOptions) -> String;
 }
 
+/// Diagram language of printers supporting several
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    PlantUml,
+    Mermaid,
+}
+
+/// Format of printers writing tables
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TableFormat {
+    #[default]
+    Markdown,
+    Csv,
+    PlantUml,
+    Html,
+}
+
 #[derive(Clone)]
 pub struct PrintOptions {
     pub relations: bool,
+    /// Draw dependencies to the types in function signatures
+    pub signature_edges: bool,
     pub module_color: String,
     pub trait_color: String,
     pub functions_private: bool,
+    /// Draw the content of dependency crates instead of collapsed packages
+    pub expand_dependencies: bool,
+    /// Draw "uses" edges from modules to the macros they invoke
+    pub macro_edges: bool,
+    /// Draw traits outside of the diagram as stub interfaces instead of text
+    pub external_trait_stubs: bool,
+    /// Draw the used proc macros grouped by crate with edges from the annotated items
+    pub proc_macros: bool,
+    /// Function to start from, e.g. `crate::module::Type::method`
+    pub function: Option<String>,
+    /// Maximum number of calls to follow from `function`
+    pub depth: Option<usize>,
+    /// Enum of the state machine diagram, e.g. `module::State`
+    pub state_enum: Option<String>,
+    /// Crates drawn as external systems in the C4 diagram
+    pub external: Vec<String>,
+    /// Crates drawn as databases in the C4 diagram
+    pub databases: Vec<String>,
+    /// Show the items gated by each feature in the feature graph
+    pub feature_items: bool,
+    pub format: Format,
+    pub table_format: TableFormat,
 }
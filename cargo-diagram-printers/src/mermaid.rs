@@ -0,0 +1,315 @@
+//! Mermaid class diagram printer
+//!
+//! Mermaid has no nested packages, every module becomes a flat namespace. Generic
+//! arguments are written with `~` as Mermaid expects, e.g. `Vec~String~`.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::{
+    compact_tokens, FunctionInfo, ModuleInfo, ModulesVisitor,
+};
+use cargo_diagram_visitors::relations::{
+    field_relations, signature_dependencies, Ownership, Relation, SignatureDependency,
+};
+
+use crate::uml::{trait_edges, TraitTarget};
+use crate::{PrintOptions, Printer};
+
+struct MermaidPrinter;
+
+impl Printer for MermaidPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let mut content = String::from("classDiagram\n");
+        content.push_str("direction LR\n");
+
+        let mut traits = Vec::new();
+        let mut modules = Vec::new();
+        for (module, info) in &visitor.module_map {
+            let is_root = module.ends_with("__lib") || module.ends_with("__main");
+            if visitor.dependency_modules.contains(module) && !opt.expand_dependencies {
+                // Collapsed dependency crate
+                if is_root {
+                    let name = module
+                        .rsplit_once("__")
+                        .map_or(module.as_str(), |(name, _)| name);
+                    content.push_str(&format!(
+                        "class {}[\"{}\"] {{\n  <<dependency>>\n}}\n",
+                        class_id(module, "crate"),
+                        name
+                    ));
+                    modules.push(class_id(module, "crate"));
+                }
+                continue;
+            }
+            add_module(&mut content, module, info, visitor, &opt);
+            traits.extend(info.traits.iter().map(|info| class_id(module, &info.name)));
+            if has_module_functions(info, &opt) {
+                modules.push(class_id(module, "mod"));
+            }
+        }
+
+        add_trait_relations(&mut content, visitor, &opt, &mut traits);
+
+        if opt.relations {
+            for relation in field_relations(visitor, opt.expand_dependencies) {
+                content.push_str(&relation_edge(&relation));
+            }
+        }
+
+        if opt.signature_edges {
+            let uses =
+                signature_dependencies(visitor, opt.expand_dependencies, opt.functions_private);
+            for dependency in uses {
+                content.push_str(&signature_edge(&dependency));
+            }
+        }
+
+        // Plantuml colors are mostly CSS color names
+        content.push_str(&format!(
+            "classDef trait fill:{}\n",
+            css_color(&opt.trait_color)
+        ));
+        content.push_str(&format!(
+            "classDef module fill:{}\n",
+            css_color(&opt.module_color)
+        ));
+        if !traits.is_empty() {
+            content.push_str(&format!("cssClass \"{}\" trait\n", traits.join(",")));
+        }
+        if !modules.is_empty() {
+            content.push_str(&format!("cssClass \"{}\" module\n", modules.join(",")));
+        }
+        content
+    }
+}
+
+/// Print the overview as Mermaid class diagram
+pub fn print_mermaid_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let content = MermaidPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Namespace of a module with its structs, enums, traits and functions
+fn add_module(
+    content: &mut String,
+    module: &str,
+    info: &ModuleInfo,
+    visitor: &ModulesVisitor,
+    opt: &PrintOptions,
+) {
+    let mut classes = String::new();
+
+    for (struct_name, struct_info) in &info.structs {
+        classes.push_str(&format!(
+            "  class {}[\"{}\"] {{\n    <<struct>>\n",
+            class_id(module, struct_name),
+            struct_name
+        ));
+        for member in &struct_info.members {
+            classes.push_str(&format!(
+                "    {}: {}\n",
+                member.name,
+                mermaid_type(&member.member_type)
+            ));
+        }
+        add_functions(&mut classes, &struct_info.functions, opt);
+        classes.push_str("  }\n");
+    }
+
+    for (enum_name, enum_info) in &info.enums {
+        classes.push_str(&format!(
+            "  class {}[\"{}\"] {{\n    <<enumeration>>\n",
+            class_id(module, enum_name),
+            enum_name
+        ));
+        for variant in &enum_info.variants {
+            classes.push_str(&format!("    {}\n", variant));
+        }
+        add_functions(&mut classes, &enum_info.functions, opt);
+        classes.push_str("  }\n");
+    }
+
+    for trait_info in &info.traits {
+        classes.push_str(&format!(
+            "  class {}[\"{}\"] {{\n    <<interface>>\n",
+            class_id(module, &trait_info.name),
+            trait_info.name
+        ));
+        add_functions(&mut classes, &trait_info.functions, opt);
+        classes.push_str("  }\n");
+    }
+
+    if has_module_functions(info, opt) {
+        classes.push_str(&format!(
+            "  class {}[\"{}\"] {{\n    <<module>>\n",
+            class_id(module, "mod"),
+            visitor.module_path(module)
+        ));
+        add_functions(&mut classes, &info.functions, opt);
+        classes.push_str("  }\n");
+    }
+
+    // Mermaid rejects empty namespaces
+    if !classes.is_empty() {
+        content.push_str(&format!("namespace {} {{\n", module.replace('.', "_")));
+        content.push_str(&classes);
+        content.push_str("}\n");
+    }
+}
+
+/// Methods with `+` for public and `-` for private visibility
+fn add_functions(content: &mut String, functions: &[FunctionInfo], opt: &PrintOptions) {
+    for function in functions {
+        if !opt.functions_private && !function.public {
+            continue;
+        }
+        let visibility = if function.public { "+" } else { "-" };
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                format!(
+                    "{}: {}",
+                    parameter.name,
+                    mermaid_type(&parameter.param_type)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let return_type = match function.return_type.as_str() {
+            "()" => String::new(),
+            return_type => format!(" {}", mermaid_type(return_type)),
+        };
+        content.push_str(&format!(
+            "    {}{}({}){}\n",
+            visibility, function.name, parameters, return_type
+        ));
+    }
+}
+
+fn has_module_functions(info: &ModuleInfo, opt: &PrintOptions) -> bool {
+    info.functions
+        .iter()
+        .any(|function| function.public || opt.functions_private)
+}
+
+/// Realization (`..|>`) and supertrait (`--|>`) arrows, stub classes for other traits
+fn add_trait_relations(
+    content: &mut String,
+    visitor: &ModulesVisitor,
+    opt: &PrintOptions,
+    traits: &mut Vec<String>,
+) {
+    let mut stubs = BTreeMap::new();
+    let mut lines = Vec::new();
+    for edge in trait_edges(visitor, opt) {
+        let target = match &edge.target {
+            TraitTarget::Drawn(module, name) => class_id(module, name),
+            TraitTarget::Stub(path) => {
+                let id = format!("external_traits__{}", path.replace("::", "_"));
+                stubs.insert(id.clone(), path.clone());
+                id
+            }
+        };
+        let arrow = if edge.supertrait { "--|>" } else { "..|>" };
+        let from = class_id(&edge.from_module, &edge.from_type);
+        lines.push(format!("{} {} {}\n", from, arrow, target));
+    }
+
+    for (id, path) in stubs {
+        content.push_str(&format!(
+            "class {}[\"{}\"] {{\n  <<external>>\n}}\n",
+            id, path
+        ));
+        traits.push(id);
+    }
+    for line in lines {
+        content.push_str(&line);
+    }
+}
+
+/// Field relation: `*--` owned, `o--` shared and `-->` referenced
+fn relation_edge(relation: &Relation) -> String {
+    let arrow = match relation.ownership {
+        Ownership::Composition => "*--",
+        Ownership::Aggregation => "o--",
+        Ownership::Association => "-->",
+    };
+    let multiplicity = match relation.multiplicity.label() {
+        "" => String::new(),
+        label => format!(" \"{}\"", label),
+    };
+    let label = match relation.dispatch {
+        Some(dispatch) => format!("{} ({})", relation.field, dispatch.label()),
+        None => relation.field.clone(),
+    };
+    format!(
+        "{} {}{} {} : {}\n",
+        class_id(&relation.from_module, &relation.from_type),
+        arrow,
+        multiplicity,
+        class_id(&relation.to_module, &relation.to_type),
+        label
+    )
+}
+
+fn signature_edge(dependency: &SignatureDependency) -> String {
+    let from = class_id(
+        &dependency.from_module,
+        dependency.from_type.as_deref().unwrap_or("mod"),
+    );
+    let to = class_id(&dependency.to_module, &dependency.to_type);
+    match dependency.dispatch {
+        Some(dispatch) => format!("{} ..> {} : {}\n", from, to, dispatch.label()),
+        None => format!("{} ..> {}\n", from, to),
+    }
+}
+
+/// Mermaid identifier of a type, `mod` for the functions of the module
+fn class_id(module: &str, name: &str) -> String {
+    format!("{}__{}", module.replace('.', "_"), name)
+}
+
+/// Type with `~` for generic arguments, e.g. `HashMap~String; Vec~u8~~`
+///
+/// Mermaid splits members at commas inside of `~..~`, they are written as `; ` instead.
+fn mermaid_type(type_name: &str) -> String {
+    let mut depth = 0;
+    let mut mermaid = String::new();
+    let type_name = compact_tokens(type_name).replace("->", "→");
+    let mut chars = type_name.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                depth += 1;
+                mermaid.push('~');
+            }
+            '>' => {
+                depth -= 1;
+                mermaid.push('~');
+            }
+            ',' if depth > 0 => {
+                mermaid.push_str("; ");
+                while chars.next_if_eq(&' ').is_some() {}
+            }
+            c => mermaid.push(c),
+        }
+    }
+    mermaid
+}
+
+/// CSS color of a Plantuml color, `#lightskyblue` becomes `lightskyblue`
+pub(crate) fn css_color(color: &str) -> String {
+    let name = color.trim_start_matches('#');
+    let is_hex = matches!(name.len(), 3 | 6) && name.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex {
+        format!("#{}", name)
+    } else {
+        name.to_string()
+    }
+}
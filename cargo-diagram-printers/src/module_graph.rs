@@ -0,0 +1,88 @@
+//! Plantuml graph of the dependencies between modules
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_graph::ModuleGraph;
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+
+use crate::{PrintOptions, Printer};
+
+struct ModuleGraphPrinter;
+
+impl Printer for ModuleGraphPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let graph = ModuleGraph::build(visitor, opt.expand_dependencies);
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+
+        // Modules grouped by crate
+        let mut crates: BTreeMap<String, Vec<&String>> = BTreeMap::new();
+        for module in &graph.modules {
+            let root = visitor.root_module(module);
+            crates
+                .entry(visitor.module_path(&root))
+                .or_default()
+                .push(module);
+        }
+        for (crate_name, modules) in &crates {
+            uml_content.push_str(&format!(
+                "package \"{}\" <<crate>> {} {{\n",
+                crate_name, opt.module_color
+            ));
+            for module in modules {
+                let label = match (module.ends_with("__lib"), module.ends_with("__main")) {
+                    (true, _) => format!("{} (lib)", crate_name),
+                    (_, true) => format!("{} (main)", crate_name),
+                    _ => visitor.module_path(module),
+                };
+                uml_content.push_str(&format!("  rectangle \"{}\" as {}\n", label, module));
+            }
+            uml_content.push_str("}\n");
+        }
+
+        for crate_name in &graph.crates {
+            uml_content.push_str(&format!(
+                "node \"{}\" as extern__{} <<external>>\n",
+                crate_name, crate_name
+            ));
+        }
+
+        // Internal edges are solid, edges leaving the crate dashed and cycles red
+        for (edge, count) in &graph.edges {
+            let to = if graph.crates.contains(&edge.to) {
+                format!("extern__{}", edge.to)
+            } else {
+                edge.to.clone()
+            };
+            let line = if edge.external { "." } else { "-" };
+            let color = if graph.cycles.contains(edge) {
+                "[#red]"
+            } else {
+                ""
+            };
+            uml_content.push_str(&format!(
+                "{} {}{}{}> {} : {}\n",
+                edge.from, line, color, line, to, count
+            ));
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the dependencies between the modules with their reference counts (Plantuml)
+pub fn print_module_graph_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let uml_content = ModuleGraphPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
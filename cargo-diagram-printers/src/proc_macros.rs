@@ -0,0 +1,60 @@
+//! Markdown report of the used proc macros
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::proc_macros::group_proc_macros;
+
+use crate::{PrintOptions, Printer};
+
+struct ProcMacroReportPrinter;
+
+impl Printer for ProcMacroReportPrinter {
+    fn print(visitor: &ModulesVisitor, _opt: PrintOptions) -> String {
+        let mut report = String::from("# Proc macros\n");
+
+        let groups = group_proc_macros(visitor);
+        if groups.is_empty() {
+            report.push_str("\nNo derive or attribute macros found.\n");
+        }
+
+        for (crate_name, macros) in groups {
+            report.push_str(&format!("\n## {}\n", crate_name));
+            for proc_macro in macros {
+                report.push_str(&format!(
+                    "\n### `{}`\n\n",
+                    macro_label(&proc_macro.name, proc_macro.derive)
+                ));
+                report.push_str("| Module | Item |\n| --- | --- |\n");
+                for user in &proc_macro.users {
+                    report.push_str(&format!("| {} | `{}` |\n", user.module, user.item));
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Print a markdown report of the derive and attribute macros, grouped by macro crate
+pub fn print_proc_macro_report_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let report = ProcMacroReportPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(report.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// How the macro is written on an item, e.g. `#[derive(Serialize)]`
+pub(crate) fn macro_label(name: &str, derive: bool) -> String {
+    if derive {
+        format!("#[derive({})]", name)
+    } else {
+        format!("#[{}]", name)
+    }
+}
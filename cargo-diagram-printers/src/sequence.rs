@@ -0,0 +1,126 @@
+//! Sequence diagram of the calls starting at a function (Plantuml or Mermaid)
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::call_graph::CallGraph;
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::sequence::{Message, Sequence};
+
+use crate::{Format, PrintOptions, Printer};
+
+struct SequencePrinter;
+
+impl Printer for SequencePrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let function = opt.function.as_deref().unwrap_or_default();
+        let graph = CallGraph::build(visitor, opt.expand_dependencies);
+        let start = graph.find(visitor, function).into_iter().next();
+
+        let mut content = String::new();
+        match opt.format {
+            Format::PlantUml => content.push_str("@startuml\n"),
+            Format::Mermaid => content.push_str("sequenceDiagram\n"),
+        }
+
+        match start {
+            Some(start) => {
+                let sequence = Sequence::build(visitor, &graph, &start, opt.depth);
+                let title = start.path(visitor);
+                match opt.format {
+                    Format::PlantUml => content.push_str(&format!("title {}\n", title)),
+                    Format::Mermaid => content.push_str(&format!("  title {}\n", title)),
+                }
+
+                for participant in &sequence.participants {
+                    match opt.format {
+                        Format::PlantUml => content.push_str(&format!(
+                            "participant \"{}\" as {}\n",
+                            participant.name, participant.id
+                        )),
+                        Format::Mermaid => content.push_str(&format!(
+                            "  participant {} as {}\n",
+                            participant.id, participant.name
+                        )),
+                    }
+                }
+                add_messages(&mut content, &sequence.messages, opt.format, 1);
+            }
+            None => match opt.format {
+                Format::PlantUml => content.push_str(&format!("title {} (not found)\n", function)),
+                Format::Mermaid => content.push_str(&format!("  title {} (not found)\n", function)),
+            },
+        }
+
+        if opt.format == Format::PlantUml {
+            content.push_str("@enduml\n");
+        }
+        content
+    }
+}
+
+/// Print the calls starting at `opt.function` as sequence diagram (Plantuml or Mermaid)
+pub fn print_sequence_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let content = SequencePrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn add_messages(content: &mut String, messages: &[Message], format: Format, level: usize) {
+    // Plantuml does not need the indentation, it only helps reading the file
+    let indent = "  ".repeat(level);
+
+    for message in messages {
+        match message {
+            Message::Call {
+                from,
+                to,
+                function,
+                nested,
+            } => {
+                match format {
+                    Format::PlantUml => content
+                        .push_str(&format!("{}{} -> {} : {}()\n", indent, from, to, function)),
+                    Format::Mermaid => {
+                        content.push_str(&format!("{}{}->>{}: {}()\n", indent, from, to, function))
+                    }
+                }
+                if !nested.is_empty() {
+                    content.push_str(&format!("{}activate {}\n", indent, to));
+                    add_messages(content, nested, format, level + 1);
+                    content.push_str(&format!("{}deactivate {}\n", indent, to));
+                }
+            }
+            Message::Alt(branches) => {
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    let keyword = if i == 0 { "alt" } else { "else" };
+                    content.push_str(&format!(
+                        "{}{} {}\n",
+                        indent,
+                        keyword,
+                        label(condition, format)
+                    ));
+                    add_messages(content, body, format, level + 1);
+                }
+                content.push_str(&format!("{}end\n", indent));
+            }
+            Message::Loop { header, body } => {
+                content.push_str(&format!("{}loop {}\n", indent, label(header, format)));
+                add_messages(content, body, format, level + 1);
+                content.push_str(&format!("{}end\n", indent));
+            }
+        }
+    }
+}
+
+/// Text of a block label, Mermaid ends statements at `;` and reads `#` as entity
+fn label(text: &str, format: Format) -> String {
+    match format {
+        Format::PlantUml => text.to_string(),
+        Format::Mermaid => text.replace(';', ",").replace('#', ""),
+    }
+    .trim_end()
+    .to_string()
+}
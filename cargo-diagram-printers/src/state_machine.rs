@@ -0,0 +1,57 @@
+//! Plantuml state diagram of an enum
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::state_machine::StateMachine;
+
+use crate::{PrintOptions, Printer};
+
+struct StateMachinePrinter;
+
+impl Printer for StateMachinePrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let enum_path = opt.state_enum.as_deref().unwrap_or_default();
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("hide empty description\n");
+
+        match StateMachine::build(visitor, enum_path) {
+            Some(state_machine) => {
+                uml_content.push_str(&format!("title {}\n", state_machine.name));
+                for state in &state_machine.states {
+                    uml_content.push_str(&format!("state {}\n", state));
+                }
+                if let Some(initial) = &state_machine.initial {
+                    uml_content.push_str(&format!("[*] --> {}\n", initial));
+                }
+                for transition in &state_machine.transitions {
+                    uml_content.push_str(&format!("{} --> {}", transition.from, transition.to));
+                    if !transition.trigger.is_empty() {
+                        uml_content.push_str(&format!(" : {}", transition.trigger));
+                    }
+                    uml_content.push('\n');
+                }
+            }
+            None => uml_content.push_str(&format!("title {} (not found)\n", enum_path)),
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print the states and transitions of the enum `opt.state_enum` (Plantuml)
+pub fn print_state_machine_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let uml_content = StateMachinePrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
@@ -0,0 +1,158 @@
+//! Plantuml class diagram of the structs, enums and traits without packages
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::{
+    compact_tokens, FunctionInfo, ModulesVisitor, STD_TYPES,
+};
+use cargo_diagram_visitors::relations::{field_relations, signature_dependencies};
+
+use crate::uml::{format_function_signature, relation_edge, signature_edge};
+use crate::{PrintOptions, Printer};
+
+struct StructPrinter;
+
+impl Printer for StructPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let is_drawn =
+            |module: &str| opt.expand_dependencies || !visitor.dependency_modules.contains(module);
+        let resolve_trait = |module: &str, path: &str| {
+            visitor
+                .resolve_trait(module, path)
+                .filter(|(target, _)| is_drawn(target))
+                .map(|(target, name)| class_alias(&target, &name))
+        };
+
+        let mut uml_content = String::new();
+        uml_content.push_str("@startuml\n");
+        uml_content.push_str("set namespaceSeparator none\nhide empty members\n");
+
+        let mut edges = BTreeSet::new();
+        for (module, info) in visitor
+            .module_map
+            .iter()
+            .filter(|(module, _)| is_drawn(module))
+        {
+            for (struct_name, struct_info) in &info.structs {
+                if STD_TYPES.contains(&struct_name.as_str()) {
+                    continue;
+                }
+                let alias = class_alias(module, struct_name);
+                uml_content.push_str(&format!(
+                    "class \"{}\" as {} <<struct>> {{\n",
+                    struct_name, alias
+                ));
+                for member in &struct_info.members {
+                    uml_content.push_str(&format!(
+                        "  {}: {}\n",
+                        member.name,
+                        compact_tokens(&member.member_type)
+                    ));
+                }
+                add_functions(&mut uml_content, &struct_info.functions, &opt);
+                uml_content.push_str("}\n");
+
+                for impl_trait in &struct_info.impl_traits {
+                    if let Some(target) = resolve_trait(module, impl_trait) {
+                        edges.insert(format!("{} ..|> {}", alias, target));
+                    }
+                }
+            }
+
+            for (enum_name, enum_info) in &info.enums {
+                let alias = class_alias(module, enum_name);
+                uml_content.push_str(&format!("enum \"{}\" as {} {{\n", enum_name, alias));
+                for variant in &enum_info.variants {
+                    uml_content.push_str(&format!("  {}\n", variant));
+                }
+                add_functions(&mut uml_content, &enum_info.functions, &opt);
+                uml_content.push_str("}\n");
+
+                for impl_trait in &enum_info.impl_traits {
+                    if let Some(target) = resolve_trait(module, impl_trait) {
+                        edges.insert(format!("{} ..|> {}", alias, target));
+                    }
+                }
+            }
+
+            for trait_info in &info.traits {
+                let alias = class_alias(module, &trait_info.name);
+                uml_content.push_str(&format!(
+                    "interface \"{}\" as {} {} {{\n",
+                    trait_info.name, alias, opt.trait_color
+                ));
+                add_functions(&mut uml_content, &trait_info.functions, &opt);
+                uml_content.push_str("}\n");
+
+                for supertrait in &trait_info.supertraits {
+                    if let Some(target) = resolve_trait(module, supertrait) {
+                        edges.insert(format!("{} --|> {}", alias, target));
+                    }
+                }
+            }
+        }
+
+        for relation in field_relations(visitor, opt.expand_dependencies) {
+            let from = class_alias(&relation.from_module, &relation.from_type);
+            let to = class_alias(&relation.to_module, &relation.to_type);
+            edges.insert(relation_edge(&relation, &from, &to));
+        }
+
+        if opt.signature_edges {
+            let uses =
+                signature_dependencies(visitor, opt.expand_dependencies, opt.functions_private);
+            for dependency in uses {
+                // Free functions have no class in this diagram
+                if let Some(owner) = &dependency.from_type {
+                    let from = class_alias(&dependency.from_module, owner);
+                    let to = class_alias(&dependency.to_module, &dependency.to_type);
+                    edges.insert(signature_edge(&dependency, &from, &to));
+                }
+            }
+        }
+
+        for edge in edges {
+            uml_content.push_str(&edge);
+            uml_content.push('\n');
+        }
+
+        uml_content.push_str("@enduml\n");
+        uml_content
+    }
+}
+
+/// Print a class diagram of the structs, enums and traits without packages (Plantuml)
+pub fn print_structs_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: PrintOptions) {
+    let uml_content = StructPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(uml_content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn add_functions(uml_content: &mut String, functions: &[FunctionInfo], opt: &PrintOptions) {
+    let functions: Vec<&FunctionInfo> = functions
+        .iter()
+        .filter(|function| opt.functions_private || function.public)
+        .collect();
+    if functions.is_empty() {
+        return;
+    }
+
+    uml_content.push_str("  --\n");
+    for function in functions {
+        let prefix = if function.public { "+" } else { "-" };
+        uml_content.push_str(&format!(
+            "  {} {}\n",
+            prefix,
+            format_function_signature(function)
+        ));
+    }
+}
+
+/// Alias of a type, unique across modules
+fn class_alias(module: &str, name: &str) -> String {
+    format!("{}__{}", module.replace('.', "_"), name)
+}
@@ -0,0 +1,165 @@
+//! Table of the traits implemented by the types as Markdown, CSV, Plantuml or HTML
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::trait_matrix::{MatrixRow, TraitMatrix};
+
+use crate::{PrintOptions, Printer, TableFormat};
+
+struct TraitMatrixPrinter;
+
+impl Printer for TraitMatrixPrinter {
+    fn print(visitor: &ModulesVisitor, opt: PrintOptions) -> String {
+        let matrix = TraitMatrix::build(visitor, opt.expand_dependencies);
+        match opt.table_format {
+            TableFormat::Markdown => print_markdown(&matrix),
+            TableFormat::Csv => print_csv(&matrix),
+            TableFormat::PlantUml => print_plantuml(&matrix, &opt),
+            TableFormat::Html => print_html(&matrix),
+        }
+    }
+}
+
+/// Print the table of the traits implemented by the types
+pub fn print_trait_matrix_to_file(
+    visitor: &ModulesVisitor,
+    output_path: &PathBuf,
+    opt: PrintOptions,
+) {
+    let content = TraitMatrixPrinter::print(visitor, opt);
+
+    let mut file = File::create(output_path).expect("Unable to create file");
+    file.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+/// Cells of a row in column order, empty if the trait is not implemented
+fn cells<'a>(
+    matrix: &'a TraitMatrix,
+    row: &'a MatrixRow,
+) -> impl Iterator<Item = &'static str> + 'a {
+    matrix.traits.iter().map(|name| {
+        row.traits
+            .get(name)
+            .map(|kind| kind.label())
+            .unwrap_or_default()
+    })
+}
+
+fn type_label(row: &MatrixRow) -> String {
+    if row.is_enum {
+        format!("{} (enum)", row.type_path)
+    } else {
+        row.type_path.clone()
+    }
+}
+
+fn print_markdown(matrix: &TraitMatrix) -> String {
+    let mut report = String::from("# Trait implementations\n\n");
+    if matrix.rows.is_empty() {
+        report.push_str("No types found.\n");
+        return report;
+    }
+
+    report.push_str("| Type |");
+    for name in &matrix.traits {
+        report.push_str(&format!(" {} |", name));
+    }
+    report.push_str("\n| --- |");
+    report.push_str(&" :---: |".repeat(matrix.traits.len()));
+    report.push('\n');
+
+    for row in &matrix.rows {
+        report.push_str(&format!("| `{}` |", type_label(row)));
+        for cell in cells(matrix, row) {
+            report.push_str(&format!(" {} |", cell));
+        }
+        report.push('\n');
+    }
+    report
+}
+
+fn print_csv(matrix: &TraitMatrix) -> String {
+    let mut header = vec!["type".to_string(), "kind".to_string()];
+    header.extend(matrix.traits.iter().map(|name| csv_field(name)));
+
+    let mut content = header.join(",");
+    content.push('\n');
+    for row in &matrix.rows {
+        let kind = if row.is_enum { "enum" } else { "struct" };
+        let mut fields = vec![csv_field(&row.type_path), kind.to_string()];
+        fields.extend(cells(matrix, row).map(str::to_string));
+        content.push_str(&fields.join(","));
+        content.push('\n');
+    }
+    content
+}
+
+/// Quote fields containing separators or quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Creole table in a note, implemented traits are highlighted with the trait color
+fn print_plantuml(matrix: &TraitMatrix, opt: &PrintOptions) -> String {
+    let mut uml_content = String::from("@startuml\n");
+    uml_content.push_str("note as matrix\n");
+
+    uml_content.push_str("|= Type |");
+    for name in &matrix.traits {
+        uml_content.push_str(&format!("= {} |", name));
+    }
+    uml_content.push('\n');
+
+    for row in &matrix.rows {
+        uml_content.push_str(&format!("| {} |", type_label(row)));
+        for cell in cells(matrix, row) {
+            if cell.is_empty() {
+                uml_content.push_str(" |");
+            } else {
+                uml_content.push_str(&format!("<{}> {} |", opt.trait_color, cell));
+            }
+        }
+        uml_content.push('\n');
+    }
+
+    uml_content.push_str("end note\n");
+    uml_content.push_str("@enduml\n");
+    uml_content
+}
+
+fn print_html(matrix: &TraitMatrix) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Trait implementations</title>\n",
+    );
+    html.push_str(
+        "<style>\ntable { border-collapse: collapse; }\nth, td { border: 1px solid #ccc; padding: 2px 6px; }\ntd.derive { background: #e0f0ff; }\ntd.impl { background: #e0ffe0; }\ntd.blanket { background: #fff0d0; }\n</style>\n",
+    );
+    html.push_str("</head>\n<body>\n<table>\n<tr><th>Type</th>");
+    for name in &matrix.traits {
+        html.push_str(&format!("<th>{}</th>", escape_html(name)));
+    }
+    html.push_str("</tr>\n");
+
+    for row in &matrix.rows {
+        html.push_str(&format!("<tr><td>{}</td>", escape_html(&type_label(row))));
+        for cell in cells(matrix, row) {
+            html.push_str(&format!("<td class=\"{}\">{}</td>", cell, cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
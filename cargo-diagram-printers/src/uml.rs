@@ -1,10 +1,16 @@
 //! Plantuml Uml printer
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
 use cargo_diagram_visitors::module_visitor::{FunctionInfo, ModulesVisitor};
+use cargo_diagram_visitors::proc_macros::group_proc_macros;
+use cargo_diagram_visitors::relations::{
+    field_relations, signature_dependencies, Ownership, Relation, SignatureDependency,
+};
 
+use crate::proc_macros::macro_label;
 use crate::{PrintOptions, Printer};
 
 struct UMLPrinter;
@@ -21,10 +27,19 @@ impl Printer for UMLPrinter {
         for (module, info) in &visitor.module_map {
             if module.ends_with("__lib") || module.ends_with("__main") {
                 let package_name = module.replace(".", "_");
+                if visitor.dependency_modules.contains(module) && !opt.expand_dependencies {
+                    // Collapsed dependency crate
+                    uml_content.push_str(&format!(
+                        "package {} <<dependency>> {} {{\n}}\n",
+                        package_name, opt.module_color
+                    ));
+                    continue;
+                }
                 // Call recursive function to handle module and submodules
                 add_module_to_uml(
                     &mut uml_content,
                     package_name,
+                    module,
                     info,
                     visitor,
                     0,
@@ -33,9 +48,23 @@ impl Printer for UMLPrinter {
             }
         }
 
+        add_trait_relations(&mut uml_content, visitor, &opt);
+
         if opt.relations {
             // Add relationships (arrows) between structs, enums, and their members
-            add_relations(&mut uml_content, visitor);
+            add_relations(&mut uml_content, visitor, &opt);
+        }
+
+        if opt.signature_edges {
+            add_signature_edges(&mut uml_content, visitor, &opt);
+        }
+
+        if opt.macro_edges {
+            add_macro_edges(&mut uml_content, visitor, &opt);
+        }
+
+        if opt.proc_macros {
+            add_proc_macros(&mut uml_content, visitor, &opt);
         }
         // End the UML diagram
         uml_content.push_str("@enduml\n");
@@ -58,6 +87,7 @@ pub fn print_uml_to_file(visitor: &ModulesVisitor, output_path: &PathBuf, opt: P
 fn add_module_to_uml(
     uml_content: &mut String,
     package_name: String,
+    module: &str,
     info: &cargo_diagram_visitors::module_visitor::ModuleInfo,
     visitor: &ModulesVisitor,
     level: usize,
@@ -80,8 +110,11 @@ fn add_module_to_uml(
     for (struct_name, struct_info) in &info.structs {
         let fully_qualified_struct_name = format!("{package_name}::{}", struct_name); // Create fully qualified name
         uml_content.push_str(&format!(
-            "{}  class {} as \"{}\" <<struct>> {{\n",
-            indent, fully_qualified_struct_name, struct_name
+            "{}  class {} as \"{}\" <<struct>>{} {{\n",
+            indent,
+            fully_qualified_struct_name,
+            struct_name,
+            macro_stereotype(struct_info.macro_generated)
         ));
 
         // Add struct description if available
@@ -97,10 +130,11 @@ fn add_module_to_uml(
             }
         }
 
-        // Add implemented traits
-        if !struct_info.impl_traits.is_empty() {
+        // Add implemented traits, drawn traits are connected by arrows instead
+        let text_traits = text_traits(visitor, module, &struct_info.impl_traits, &opt);
+        if !text_traits.is_empty() {
             uml_content.push_str(&format!("{}    .. Implements ..\n", indent));
-            for impl_trait in &struct_info.impl_traits {
+            for impl_trait in text_traits {
                 uml_content.push_str(&format!("{}    {}\n", indent, impl_trait));
             }
         }
@@ -138,10 +172,21 @@ fn add_module_to_uml(
             trait_info.name, trait_info.name
         );
         uml_content.push_str(&format!(
-            "{}  interface {} {} {{\n",
-            indent, fully_qualified_trait_name, opt.trait_color
+            "{}  interface {}{} {} {{\n",
+            indent,
+            fully_qualified_trait_name,
+            macro_stereotype(trait_info.macro_generated),
+            opt.trait_color
         ));
 
+        let text_traits = text_traits(visitor, module, &trait_info.supertraits, &opt);
+        if !text_traits.is_empty() {
+            uml_content.push_str(&format!("{}    .. Supertraits ..\n", indent));
+            for supertrait in text_traits {
+                uml_content.push_str(&format!("{}    {}\n", indent, supertrait));
+            }
+        }
+
         if !trait_info.functions.is_empty() {
             uml_content.push_str(&format!("{}    .. Functions ..\n", indent));
 
@@ -156,6 +201,24 @@ fn add_module_to_uml(
         uml_content.push_str(&format!("{}  }}\n", indent));
     }
 
+    // Add macro_rules! definitions
+    for macro_info in &info.macros {
+        uml_content.push_str(&format!(
+            "{}  class {} as \"{}!\" <<macro_rules>> {{\n",
+            indent,
+            macro_class_name(&package_name, &macro_info.name),
+            macro_info.name
+        ));
+        if !macro_info.description.is_empty() {
+            uml_content.push_str(&format!("{}    ' {}\n", indent, &macro_info.description));
+        }
+        if macro_info.exported {
+            uml_content.push_str(&format!("{}    #[macro_export]\n", indent));
+        }
+        uml_content.push_str(&format!("{}    arms: {}\n", indent, macro_info.arms));
+        uml_content.push_str(&format!("{}  }}\n", indent));
+    }
+
     // Add public functions of the module (inside mod.rs-like class)
     if !info.functions.is_empty() {
         let result: Option<&FunctionInfo> = info.functions.iter().find(|f| f.public);
@@ -179,6 +242,7 @@ fn add_module_to_uml(
             add_module_to_uml(
                 uml_content,
                 sub_package_name,
+                submodule,
                 sub_info,
                 visitor,
                 level + 1,
@@ -207,42 +271,352 @@ fn print_functions(
         }
 
         let prefix = if function.public { "+" } else { "-" };
-        uml_content.push_str(&format!("{}    {prefix} {}\n", indent, fn_signature));
+        uml_content.push_str(&format!(
+            "{}    {prefix} {}{}\n",
+            indent,
+            fn_signature,
+            macro_stereotype(function.macro_generated)
+        ));
     }
 }
 
-/// Add relationships between structs and enums based on their members
-fn add_relations(uml_content: &mut String, visitor: &ModulesVisitor) {
-    // Iterate through all modules and structs to identify relations
-    for (module, info) in &visitor.module_map {
-        let package_name = module.replace(".", "_");
+/// Stereotype of items generated by macros
+fn macro_stereotype(macro_generated: bool) -> &'static str {
+    if macro_generated {
+        " <<macro>>"
+    } else {
+        ""
+    }
+}
+
+/// Drawn trait of a trait path used in a module, its module and name
+fn drawn_trait(
+    visitor: &ModulesVisitor,
+    module: &str,
+    path: &str,
+    opt: &PrintOptions,
+) -> Option<(String, String)> {
+    visitor.resolve_trait(module, path).filter(|(target, _)| {
+        opt.expand_dependencies || !visitor.dependency_modules.contains(target)
+    })
+}
+
+/// Traits listed as text, the ones without an interface in the diagram
+fn text_traits<'a>(
+    visitor: &ModulesVisitor,
+    module: &str,
+    traits: &'a [String],
+    opt: &PrintOptions,
+) -> Vec<&'a String> {
+    if opt.external_trait_stubs {
+        return Vec::new();
+    }
+    traits
+        .iter()
+        .filter(|path| drawn_trait(visitor, module, path, opt).is_none())
+        .collect()
+}
 
-        // Process structs
+/// Target of a realization or supertrait arrow
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TraitTarget {
+    /// A trait of the diagram with its module and name
+    Drawn(String, String),
+    /// Path of a trait outside of the diagram, only with `external_trait_stubs`
+    Stub(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TraitEdge {
+    pub from_module: String,
+    pub from_type: String,
+    pub target: TraitTarget,
+    /// Supertrait (`--|>`) instead of implemented trait (`..|>`)
+    pub supertrait: bool,
+}
+
+/// Realization and supertrait arrows of the drawn structs, enums and traits
+pub(crate) fn trait_edges(visitor: &ModulesVisitor, opt: &PrintOptions) -> Vec<TraitEdge> {
+    let mut edges = Vec::new();
+    let mut add_edge = |module: &String, from_type: &String, path: &String, supertrait| {
+        let target = match drawn_trait(visitor, module, path, opt) {
+            Some((target, name)) => TraitTarget::Drawn(target, name),
+            None if opt.external_trait_stubs => {
+                let path = path.split('<').next().unwrap_or(path).trim();
+                TraitTarget::Stub(path.to_string())
+            }
+            None => return,
+        };
+        let edge = TraitEdge {
+            from_module: module.clone(),
+            from_type: from_type.clone(),
+            target,
+            supertrait,
+        };
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    };
+
+    for (module, info) in &visitor.module_map {
+        if visitor.dependency_modules.contains(module) && !opt.expand_dependencies {
+            continue;
+        }
         for (struct_name, struct_info) in &info.structs {
-            let struct_class_name_root = format!("{package_name}::{}", struct_name); // Fully qualified struct name
+            for impl_trait in &struct_info.impl_traits {
+                add_edge(module, struct_name, impl_trait, false);
+            }
+        }
+        for (enum_name, enum_info) in &info.enums {
+            for impl_trait in &enum_info.impl_traits {
+                add_edge(module, enum_name, impl_trait, false);
+            }
+        }
+        for trait_info in &info.traits {
+            for supertrait in &trait_info.supertraits {
+                add_edge(module, &trait_info.name, supertrait, true);
+            }
+        }
+    }
+    edges
+}
 
-            // Check each member of the struct to find if it's another struct or enum
-            for member in &struct_info.members {
-                let member_type_path = member.member_type.replace("::", "_");
-
-                for (struct_name, _struct_info) in &info.structs {
-                    let struct_class_name = format!("{package_name}::{}", struct_name);
-                    // Fully qualified struct name
-
-                    if member_type_path.contains(struct_name) {
-                        uml_content.push_str(&format!(
-                            "{} --> {}\n",
-                            struct_class_name_root, struct_class_name
-                        ));
-                    }
+/// Add realization (`..|>`) and supertrait (`--|>`) arrows of the structs and traits
+///
+/// Traits outside of the diagram get a stub interface with `external_trait_stubs`.
+fn add_trait_relations(uml_content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    let edges = trait_edges(visitor, opt);
+    let mut stubs = BTreeMap::new();
+    let mut lines = Vec::new();
+    // Realizations of enums are left out, the enums are not drawn
+    for edge in edges
+        .iter()
+        .filter(|edge| is_drawn_type(visitor, &edge.from_module, &edge.from_type))
+    {
+        let target = match &edge.target {
+            TraitTarget::Drawn(module, name) => format!("{}::{}", module.replace(".", "_"), name),
+            TraitTarget::Stub(path) => {
+                let alias = format!("external_traits::{}", path.replace("::", "_"));
+                stubs.insert(alias.clone(), path);
+                alias
+            }
+        };
+        let arrow = if edge.supertrait { "--|>" } else { "..|>" };
+        lines.push(format!(
+            "{}::{} {} {}",
+            edge.from_module.replace(".", "_"),
+            edge.from_type,
+            arrow,
+            target
+        ));
+    }
+
+    for (alias, path) in stubs {
+        uml_content.push_str(&format!(
+            "interface \"{}\" as {} <<external>> {}\n",
+            path, alias, opt.trait_color
+        ));
+    }
+    for line in lines {
+        uml_content.push_str(&line);
+        uml_content.push('\n');
+    }
+}
+
+/// Whether a type is declared as class or interface, enums are not drawn in the overview
+fn is_drawn_type(visitor: &ModulesVisitor, module: &str, name: &str) -> bool {
+    visitor.module_map.get(module).is_some_and(|info| {
+        info.structs.contains_key(name) || info.traits.iter().any(|t| t.name == name)
+    })
+}
+
+/// Add relationships between structs and the types of their fields
+///
+/// Fields holding enums are left out, the enums are not drawn.
+fn add_relations(uml_content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    for relation in field_relations(visitor, opt.expand_dependencies) {
+        if !is_drawn_type(visitor, &relation.to_module, &relation.to_type) {
+            continue;
+        }
+        let from = format!(
+            "{}::{}",
+            relation.from_module.replace(".", "_"),
+            relation.from_type
+        );
+        let to = format!(
+            "{}::{}",
+            relation.to_module.replace(".", "_"),
+            relation.to_type
+        );
+        uml_content.push_str(&relation_edge(&relation, &from, &to));
+        uml_content.push('\n');
+    }
+}
+
+/// Add dashed dependency edges to the types used in function signatures
+///
+/// Only edges between drawn types are added, methods of enums and enum types are left out.
+fn add_signature_edges(uml_content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    let uses = signature_dependencies(visitor, opt.expand_dependencies, opt.functions_private);
+    for dependency in uses {
+        let is_drawn_owner = dependency
+            .from_type
+            .as_ref()
+            .is_none_or(|owner| is_drawn_type(visitor, &dependency.from_module, owner));
+        if !is_drawn_owner || !is_drawn_type(visitor, &dependency.to_module, &dependency.to_type) {
+            continue;
+        }
+        let package_name = dependency.from_module.replace(".", "_");
+        let from = match &dependency.from_type {
+            Some(owner) => format!("{package_name}::{}", owner),
+            None => format!("{package_name}_mod"),
+        };
+        let to = format!(
+            "{}::{}",
+            dependency.to_module.replace(".", "_"),
+            dependency.to_type
+        );
+        uml_content.push_str(&signature_edge(&dependency, &from, &to));
+        uml_content.push('\n');
+    }
+}
+
+/// Edge of a field relation: `*--` owned, `o--` shared and `-->` referenced
+pub(crate) fn relation_edge(relation: &Relation, from: &str, to: &str) -> String {
+    let arrow = match relation.ownership {
+        Ownership::Composition => "*--",
+        Ownership::Aggregation => "o--",
+        Ownership::Association => "-->",
+    };
+    let label = match relation.dispatch {
+        Some(dispatch) => format!("{} <<{}>>", relation.field, dispatch.label()),
+        None => relation.field.clone(),
+    };
+    match relation.multiplicity.label() {
+        "" => format!("{} {} {} : {}", from, arrow, to, label),
+        multiplicity => format!("{} {} \"{}\" {} : {}", from, arrow, multiplicity, to, label),
+    }
+}
+
+/// Edge of a signature dependency, trait objects are labelled with their dispatch
+pub(crate) fn signature_edge(dependency: &SignatureDependency, from: &str, to: &str) -> String {
+    match dependency.dispatch {
+        Some(dispatch) => format!("{} ..> {} : <<{}>>", from, to, dispatch.label()),
+        None => format!("{} ..> {}", from, to),
+    }
+}
+
+/// Add "uses" edges from modules to the macros they invoke
+fn add_macro_edges(uml_content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    let is_drawn =
+        |module: &String| opt.expand_dependencies || !visitor.dependency_modules.contains(module);
+
+    for (module, info) in visitor
+        .module_map
+        .iter()
+        .filter(|(module, _)| is_drawn(module))
+    {
+        let mut targets = Vec::new();
+
+        for invocation in &info.macro_invocations {
+            let macro_name = invocation.name.rsplit("::").next().unwrap_or_default();
+
+            // Prefer a definition in the same module, then exported ones
+            let mut definitions: Vec<(&String, bool)> = visitor
+                .module_map
+                .iter()
+                .filter(|(other, _)| is_drawn(other))
+                .flat_map(|(other, other_info)| {
+                    other_info
+                        .macros
+                        .iter()
+                        .filter(|macro_info| macro_info.name == macro_name)
+                        .map(move |macro_info| (other, macro_info.exported))
+                })
+                .collect();
+            definitions.sort_by_key(|(other, exported)| (*other != module, !exported));
+
+            if let Some((other, _)) = definitions.first() {
+                let target = macro_class_name(&other.replace(".", "_"), macro_name);
+                if !targets.contains(&target) {
+                    targets.push(target);
                 }
             }
         }
+
+        for target in targets {
+            uml_content.push_str(&format!(
+                "{} ..> {} : uses\n",
+                module.replace(".", "_"),
+                target
+            ));
+        }
     }
 }
 
+/// Add a package per proc macro crate with edges from the annotated items
+fn add_proc_macros(uml_content: &mut String, visitor: &ModulesVisitor, opt: &PrintOptions) {
+    let mut edges = Vec::new();
+
+    for (crate_name, macros) in group_proc_macros(visitor) {
+        let package_name = format!("proc_macros::{}", crate_name);
+        uml_content.push_str(&format!(
+            "package \"{}\" as {} <<proc_macro>> {} {{\n",
+            crate_name, package_name, opt.module_color
+        ));
+
+        for proc_macro in macros {
+            let kind = if proc_macro.derive {
+                "derive"
+            } else {
+                "attribute"
+            };
+            let class_name = format!("{package_name}::{}_{}", proc_macro.name, kind);
+            uml_content.push_str(&format!(
+                "  class {} as \"{}\" <<{}>>\n",
+                class_name,
+                macro_label(&proc_macro.name, proc_macro.derive),
+                kind
+            ));
+
+            for user in &proc_macro.users {
+                let source = proc_macro_user_node(visitor, &user.module, &user.owner);
+                let edge = format!("{} ..> {}\n", source, class_name);
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        uml_content.push_str("}\n");
+    }
+
+    for edge in edges {
+        uml_content.push_str(&edge);
+    }
+}
+
+/// Drawn element of an annotated item: its struct or trait, otherwise its module
+fn proc_macro_user_node(visitor: &ModulesVisitor, module: &str, owner: &str) -> String {
+    let package_name = module.replace(".", "_");
+    let drawn = visitor.module_map.get(module).is_some_and(|info| {
+        info.structs.contains_key(owner) || info.traits.iter().any(|info| info.name == owner)
+    });
+
+    if drawn {
+        format!("{package_name}::{}", owner)
+    } else {
+        package_name
+    }
+}
+
+/// Name of the class that represents a macro
+fn macro_class_name(package_name: &str, macro_name: &str) -> String {
+    format!("{package_name}::{}_macro", macro_name)
+}
+
 /// Helper function to format function signature with parameters
-fn format_function_signature(function: &FunctionInfo) -> String {
+pub(crate) fn format_function_signature(function: &FunctionInfo) -> String {
     let input_params = function
         .parameters
         .iter()
@@ -254,3 +628,131 @@ fn format_function_signature(function: &FunctionInfo) -> String {
 
     format!("{}({}){}", function.name, input_params, output_param)
 }
+
+#[cfg(test)]
+mod tests {
+    use syn::visit::Visit;
+
+    use super::*;
+    use crate::{Format, TableFormat};
+
+    const CODE: &str = "
+        pub trait Handler {}
+        pub struct Event;
+        pub enum State { Idle }
+        pub enum AppError { Io }
+        pub struct Server { state: State, event: Event }
+        impl Handler for Server {}
+        impl Handler for State {}
+        impl State {
+            pub fn next(self, event: Event) -> State { self }
+        }
+        pub fn fail() -> Result<(), AppError> { Ok(()) }
+    ";
+
+    fn overview(code: &str, opt: PrintOptions) -> String {
+        let mut file_visitor = ModulesVisitor::default();
+        file_visitor.current_module = "t__lib".to_string();
+        file_visitor.visit_file(&syn::parse_file(code).unwrap());
+        let mut visitor = ModulesVisitor::default();
+        visitor.merge(file_visitor.module_map);
+        UMLPrinter::print(&visitor, opt)
+    }
+
+    fn options() -> PrintOptions {
+        PrintOptions {
+            relations: false,
+            signature_edges: false,
+            module_color: "#lightskyblue".to_string(),
+            trait_color: "#violet".to_string(),
+            functions_private: false,
+            expand_dependencies: false,
+            macro_edges: false,
+            external_trait_stubs: false,
+            proc_macros: false,
+            function: None,
+            depth: None,
+            state_enum: None,
+            external: Vec::new(),
+            databases: Vec::new(),
+            feature_items: false,
+            format: Format::default(),
+            table_format: TableFormat::default(),
+        }
+    }
+
+    const ARROWS: &[&str] = &["..|>", "--|>", "*--", "o--", "-->", "..>"];
+
+    /// Edges of the diagram whose start or end is not declared as class or interface
+    fn undeclared_endpoints(uml: &str) -> Vec<String> {
+        let mut declared = Vec::new();
+        let mut edges = Vec::new();
+        for line in uml.lines() {
+            // The label of an edge follows ` : `
+            let line = line.split(" : ").next().unwrap_or(line);
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["class" | "interface", name, rest @ ..] => match name.starts_with('"') {
+                    // `interface "path" as alias`
+                    true => declared.extend(rest.get(1).map(|alias| alias.to_string())),
+                    false => declared.push(name.to_string()),
+                },
+                [from, arrow, .., to] if ARROWS.contains(arrow) => {
+                    edges.push((from.to_string(), to.to_string()))
+                }
+                _ => {}
+            }
+        }
+        edges
+            .into_iter()
+            .flat_map(|(from, to)| [from, to])
+            .filter(|end| !declared.contains(end))
+            .collect()
+    }
+
+    #[test]
+    fn relations_to_enums_are_skipped() {
+        let uml = overview(
+            CODE,
+            PrintOptions {
+                relations: true,
+                ..options()
+            },
+        );
+        assert!(uml.contains("t__lib::Server *-- t__lib::Event : event"));
+        assert!(!uml.contains("t__lib::Server *-- t__lib::State"));
+    }
+    #[test]
+    fn trait_edges_end_at_declared_types() {
+        let uml = overview(CODE, options());
+        assert!(uml.contains("t__lib::Server ..|> t__lib::Handler"));
+        assert_eq!(undeclared_endpoints(&uml), Vec::<String>::new());
+
+        let stubs = PrintOptions {
+            external_trait_stubs: true,
+            ..options()
+        };
+        let uml = overview("pub struct Id; impl Clone for Id {}", stubs);
+        assert!(uml.contains("t__lib::Id ..|> external_traits::Clone"));
+        assert_eq!(undeclared_endpoints(&uml), Vec::<String>::new());
+    }
+    #[test]
+    fn signature_edges_end_at_declared_types() {
+        let code = "
+            pub struct Request;
+            pub struct Server;
+            impl Server {
+                pub fn handle(&self, request: Request) -> State { State::Idle }
+            }
+            pub fn serve(server: Server) {}
+        ";
+        let opt = PrintOptions {
+            signature_edges: true,
+            ..options()
+        };
+        let uml = overview(&format!("{}{}", CODE, code), opt);
+        assert!(uml.contains("t__lib::Server ..> t__lib::Request"));
+        assert!(uml.contains("t__lib_mod ..> t__lib::Server"));
+        assert_eq!(undeclared_endpoints(&uml), Vec::<String>::new());
+    }
+}
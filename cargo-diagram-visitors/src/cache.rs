@@ -0,0 +1,484 @@
+//! Cache for per-file extraction results
+//!
+//! Every analyzed file is stored as its own entry, keyed by a hash of the tool
+//! version, the module name and the file content. Unchanged files are loaded from
+//! the cache instead of being parsed again. Entries are named after their module,
+//! storing a new entry removes the outdated ones of the module.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::Lines;
+
+use crate::module_visitor::{
+    AttributeUsage, BlanketImpl, CallInfo, EnumInfo, ErrorSource, ErrorVariant, FeatureGate,
+    FunctionInfo, MacroInfo, MacroInvocation, MemberInfo, ModuleInfo, ParameterInfo, StructInfo,
+    TraitInfo,
+};
+
+/// Version of the cache format, bump it whenever the cached model changes
+const CACHE_FORMAT: u32 = 1;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Build the cache key of a file
+pub fn cache_key(module_name: &str, code: &str) -> String {
+    let parts: [&[u8]; 4] = [
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        &CACHE_FORMAT.to_le_bytes(),
+        module_name.as_bytes(),
+        code.as_bytes(),
+    ];
+
+    // FNV-1a, stable across toolchains unlike the std hasher
+    let mut hash = FNV_OFFSET;
+    for part in parts {
+        for byte in part.iter().chain(&[0xff]) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// Load the modules of a cached file
+pub fn load(
+    cache_dir: &Path,
+    module_name: &str,
+    key: &str,
+) -> Option<BTreeMap<String, ModuleInfo>> {
+    let content = fs::read_to_string(cache_dir.join(entry_name(module_name, key))).ok()?;
+    let mut decoder = Decoder {
+        lines: content.lines(),
+    };
+
+    let modules = BTreeMap::decode(&mut decoder)?;
+    // Reject truncated or trailing data
+    decoder.lines.next().is_none().then_some(modules)
+}
+
+/// Store the modules of a file in the cache
+///
+/// Entries of the module with another key (changed content or older cache format)
+/// are removed.
+pub fn store(
+    cache_dir: &Path,
+    module_name: &str,
+    key: &str,
+    modules: &BTreeMap<String, ModuleInfo>,
+) -> std::io::Result<()> {
+    let mut encoder = Encoder::default();
+    modules.encode(&mut encoder);
+
+    fs::create_dir_all(cache_dir)?;
+    let name = entry_name(module_name, key);
+    fs::write(cache_dir.join(&name), encoder.out)?;
+
+    let prefix = format!("{}-", entry_module(module_name));
+    for entry in fs::read_dir(cache_dir)?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(stem) = file_name.strip_suffix(".cache") else {
+            continue;
+        };
+        if stem.starts_with(&prefix) && file_name != name {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// File name of an entry, e.g. `my_crate__lib-0123456789abcdef.cache`
+fn entry_name(module_name: &str, key: &str) -> String {
+    format!("{}-{}.cache", entry_module(module_name), key)
+}
+
+fn entry_module(module_name: &str) -> String {
+    module_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Encoder {
+    out: String,
+}
+
+impl Encoder {
+    fn line(&mut self, value: &str) {
+        for c in value.chars() {
+            match c {
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('\n');
+    }
+}
+
+struct Decoder<'a> {
+    lines: Lines<'a>,
+}
+
+impl Decoder<'_> {
+    fn line(&mut self) -> Option<String> {
+        let line = self.lines.next()?;
+        let mut value = String::with_capacity(line.len());
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next()? {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    '\\' => value.push('\\'),
+                    _ => return None,
+                }
+            } else {
+                value.push(c);
+            }
+        }
+        Some(value)
+    }
+}
+
+/// A value that can be written to and read from the cache
+trait Cached: Sized {
+    fn encode(&self, encoder: &mut Encoder);
+    fn decode(decoder: &mut Decoder) -> Option<Self>;
+}
+
+impl Cached for String {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.line(self);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Option<Self> {
+        decoder.line()
+    }
+}
+
+impl Cached for bool {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.line(if *self { "1" } else { "0" });
+    }
+
+    fn decode(decoder: &mut Decoder) -> Option<Self> {
+        match decoder.line()?.as_str() {
+            "1" => Some(true),
+            "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl Cached for usize {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.line(&self.to_string());
+    }
+
+    fn decode(decoder: &mut Decoder) -> Option<Self> {
+        decoder.line()?.parse().ok()
+    }
+}
+
+impl<T: Cached> Cached for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.len().encode(encoder);
+        for item in self {
+            item.encode(encoder);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Option<Self> {
+        let len = usize::decode(decoder)?;
+        (0..len).map(|_| T::decode(decoder)).collect()
+    }
+}
+
+impl<T: Cached> Cached for BTreeMap<String, T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.len().encode(encoder);
+        for (key, value) in self {
+            key.encode(encoder);
+            value.encode(encoder);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Option<Self> {
+        let len = usize::decode(decoder)?;
+        (0..len)
+            .map(|_| Some((String::decode(decoder)?, T::decode(decoder)?)))
+            .collect()
+    }
+}
+
+/// Implement `Cached` for a struct by encoding its fields in order
+macro_rules! cached_struct {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl Cached for $name {
+            fn encode(&self, encoder: &mut Encoder) {
+                $(self.$field.encode(encoder);)*
+            }
+
+            fn decode(decoder: &mut Decoder) -> Option<Self> {
+                Some($name {
+                    $($field: Cached::decode(decoder)?,)*
+                })
+            }
+        }
+    };
+}
+
+cached_struct!(ModuleInfo {
+    structs,
+    enums,
+    traits,
+    submodules,
+    functions,
+    description,
+    macros,
+    macro_invocations,
+    uses,
+    path_references,
+    attribute_usages,
+    feature_gates,
+    calls,
+    blanket_impls,
+    source_file,
+});
+cached_struct!(BlanketImpl { trait_name, bounds });
+cached_struct!(FeatureGate { feature, item });
+cached_struct!(CallInfo {
+    caller,
+    callee,
+    method,
+    on_self,
+});
+cached_struct!(AttributeUsage {
+    path,
+    derive,
+    item,
+    owner,
+});
+cached_struct!(MacroInfo {
+    name,
+    exported,
+    arms,
+    description,
+});
+cached_struct!(MacroInvocation { name, context });
+cached_struct!(TraitInfo {
+    name,
+    functions,
+    supertraits,
+    description,
+    macro_generated,
+});
+cached_struct!(StructInfo {
+    derives,
+    impl_traits,
+    functions,
+    members,
+    description,
+    macro_generated,
+    impl_only,
+    error_variants,
+});
+cached_struct!(EnumInfo {
+    variants,
+    derives,
+    impl_traits,
+    functions,
+    description,
+    macro_generated,
+    error_variants,
+});
+cached_struct!(ErrorVariant {
+    name,
+    message,
+    sources,
+});
+cached_struct!(ErrorSource {
+    field,
+    source_type,
+    from,
+});
+cached_struct!(FunctionInfo {
+    name,
+    parameters,
+    public,
+    return_type,
+    description,
+    macro_generated,
+});
+cached_struct!(ParameterInfo { name, param_type });
+cached_struct!(MemberInfo { name, member_type });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str) -> FunctionInfo {
+        FunctionInfo {
+            name: name.to_string(),
+            parameters: vec![ParameterInfo {
+                name: "value".to_string(),
+                param_type: "& Vec < u8 >".to_string(),
+            }],
+            public: true,
+            return_type: "Result < () , Error >".to_string(),
+            description: "Line one\nline two with \\ backslash".to_string(),
+            macro_generated: true,
+        }
+    }
+
+    fn error_variant() -> ErrorVariant {
+        ErrorVariant {
+            name: "Io".to_string(),
+            message: "io error: {0}".to_string(),
+            sources: vec![ErrorSource {
+                field: "0".to_string(),
+                source_type: "std :: io :: Error".to_string(),
+                from: true,
+            }],
+        }
+    }
+
+    /// A module with every field set to a value other than its default
+    fn module() -> ModuleInfo {
+        ModuleInfo {
+            structs: BTreeMap::from([(
+                "Store".to_string(),
+                StructInfo {
+                    derives: vec!["Debug".to_string()],
+                    impl_traits: vec!["Display".to_string()],
+                    functions: vec![function("open")],
+                    members: vec![MemberInfo {
+                        name: "path".to_string(),
+                        member_type: "PathBuf".to_string(),
+                    }],
+                    description: "A store".to_string(),
+                    macro_generated: true,
+                    impl_only: true,
+                    error_variants: vec![error_variant()],
+                },
+            )]),
+            enums: BTreeMap::from([(
+                "State".to_string(),
+                EnumInfo {
+                    variants: vec!["Open".to_string(), "Closed".to_string()],
+                    derives: vec!["Clone".to_string()],
+                    impl_traits: vec!["From < u8 >".to_string()],
+                    functions: vec![function("next")],
+                    description: "State\r\nof the store".to_string(),
+                    macro_generated: true,
+                    error_variants: vec![error_variant()],
+                },
+            )]),
+            traits: vec![TraitInfo {
+                name: "Backend".to_string(),
+                functions: vec![function("read")],
+                supertraits: vec!["Send".to_string()],
+                description: "A backend".to_string(),
+                macro_generated: true,
+            }],
+            submodules: vec!["app__store__sqlite".to_string()],
+            functions: vec![function("connect")],
+            description: "Storage".to_string(),
+            macros: vec![MacroInfo {
+                name: "query".to_string(),
+                exported: true,
+                arms: 3,
+                description: "Build a query".to_string(),
+            }],
+            macro_invocations: vec![MacroInvocation {
+                name: "tracing::debug".to_string(),
+                context: "Store::open".to_string(),
+            }],
+            uses: vec!["std::path::PathBuf".to_string()],
+            path_references: vec!["std::fs::File".to_string()],
+            attribute_usages: vec![AttributeUsage {
+                path: "serde::Serialize".to_string(),
+                derive: true,
+                item: "Store".to_string(),
+                owner: "Store".to_string(),
+            }],
+            feature_gates: vec![FeatureGate {
+                feature: "sqlite".to_string(),
+                item: "mod sqlite".to_string(),
+            }],
+            calls: vec![CallInfo {
+                caller: "Store::open".to_string(),
+                callee: "connect".to_string(),
+                method: true,
+                on_self: true,
+            }],
+            blanket_impls: vec![BlanketImpl {
+                trait_name: "Backend".to_string(),
+                bounds: vec!["Read".to_string()],
+            }],
+            source_file: "src/store.rs".to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_every_field() {
+        let modules = BTreeMap::from([("app__store".to_string(), module())]);
+        let mut encoder = Encoder::default();
+        modules.encode(&mut encoder);
+
+        let mut decoder = Decoder {
+            lines: encoder.out.lines(),
+        };
+        let decoded = BTreeMap::<String, ModuleInfo>::decode(&mut decoder).unwrap();
+
+        assert!(decoder.lines.next().is_none());
+        // The model types do not implement `PartialEq`
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", modules));
+    }
+
+    #[test]
+    fn truncated_entry_is_rejected() {
+        let modules = BTreeMap::from([("app__store".to_string(), module())]);
+        let mut encoder = Encoder::default();
+        modules.encode(&mut encoder);
+
+        let truncated: String = encoder.out.lines().take(20).collect::<Vec<_>>().join("\n");
+        let mut decoder = Decoder {
+            lines: truncated.lines(),
+        };
+        assert!(BTreeMap::<String, ModuleInfo>::decode(&mut decoder).is_none());
+    }
+
+    #[test]
+    fn store_removes_outdated_entries() {
+        let dir = std::env::temp_dir().join(format!("cargo-diagram-cache-{}", std::process::id()));
+        let modules = BTreeMap::from([("app__store".to_string(), module())]);
+
+        fs::create_dir_all(&dir).unwrap();
+        store(&dir, "app__store", "old", &modules).unwrap();
+        store(&dir, "app__other", "other", &modules).unwrap();
+        store(&dir, "app__store", "new", &modules).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["app__other-other.cache", "app__store-new.cache"]);
+        assert!(load(&dir, "app__store", "new").is_some());
+        assert!(load(&dir, "app__store", "old").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+//! Best-effort call graph from the calls recorded in function bodies
+//!
+//! Calls are resolved by name through the module tree, `use` statements and the
+//! known structs, enums and traits. Calls that can not be resolved uniquely, e.g. methods
+//! of std types or method names defined by several types, are left out.
+use std::collections::BTreeSet;
+
+use crate::module_visitor::{CallInfo, ModuleInfo, ModulesVisitor};
+
+/// A function of the call graph
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallNode {
+    pub module: String,
+    /// Function name, with its type for methods, e.g. `Type::method`
+    pub function: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    pub nodes: BTreeSet<CallNode>,
+    /// Caller and callee
+    pub edges: BTreeSet<(CallNode, CallNode)>,
+}
+
+impl CallGraph {
+    /// Build the call graph of all analyzed modules
+    ///
+    /// Modules of dependency crates are skipped unless `dependencies` is set.
+    pub fn build(visitor: &ModulesVisitor, dependencies: bool) -> CallGraph {
+        let resolver = Resolver {
+            visitor,
+            dependencies,
+        };
+        let mut graph = CallGraph::default();
+
+        for (module, info) in resolver.modules() {
+            for function in &info.functions {
+                graph.nodes.insert(node(module, &function.name));
+            }
+            for (type_name, functions) in info.type_functions() {
+                for function in functions {
+                    graph
+                        .nodes
+                        .insert(node(module, &format!("{}::{}", type_name, function.name)));
+                }
+            }
+
+            for call in &info.calls {
+                if let Some(callee) = resolver.resolve_call(module, call) {
+                    let caller = node(module, &call.caller);
+                    graph.nodes.insert(caller.clone());
+                    graph.edges.insert((caller, callee));
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Functions matching a path like `crate::module::Type::method`
+    ///
+    /// The path may be shortened from the front, e.g. `Type::method`.
+    pub fn find(&self, visitor: &ModulesVisitor, path: &str) -> Vec<CallNode> {
+        let path = path.strip_prefix("crate::").unwrap_or(path);
+        let suffix = format!("::{}", path);
+
+        self.nodes
+            .iter()
+            .filter(|node| {
+                let full_path = node.path(visitor);
+                full_path == path || full_path.ends_with(&suffix)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Part of the graph reachable from the start functions within `depth` calls
+    pub fn reachable(&self, starts: &[CallNode], depth: Option<usize>) -> CallGraph {
+        let mut graph = CallGraph::default();
+        let mut current: Vec<CallNode> = starts.to_vec();
+        graph.nodes.extend(current.iter().cloned());
+
+        let mut level = 0;
+        while !current.is_empty() && depth.is_none_or(|depth| level < depth) {
+            let mut next = Vec::new();
+            for caller in &current {
+                for (from, to) in self.edges.iter().filter(|(from, _)| from == caller) {
+                    graph.edges.insert((from.clone(), to.clone()));
+                    if graph.nodes.insert(to.clone()) {
+                        next.push(to.clone());
+                    }
+                }
+            }
+            current = next;
+            level += 1;
+        }
+
+        graph
+    }
+}
+
+impl CallNode {
+    /// Full path of the function, e.g. `my_crate::module::Type::method`
+    pub fn path(&self, visitor: &ModulesVisitor) -> String {
+        format!("{}::{}", visitor.module_path(&self.module), self.function)
+    }
+}
+
+fn node(module: &str, function: &str) -> CallNode {
+    CallNode {
+        module: module.to_string(),
+        function: function.to_string(),
+    }
+}
+
+struct Resolver<'a> {
+    visitor: &'a ModulesVisitor,
+    dependencies: bool,
+}
+
+impl<'a> Resolver<'a> {
+    fn modules(&self) -> impl Iterator<Item = (&'a String, &'a ModuleInfo)> + '_ {
+        self.visitor.module_map.iter().filter(|(module, _)| {
+            self.dependencies || !self.visitor.dependency_modules.contains(*module)
+        })
+    }
+
+    fn resolve_call(&self, module: &str, call: &CallInfo) -> Option<CallNode> {
+        // `Type::method` callers own their methods, plain functions own nothing
+        let owner = call
+            .caller
+            .split_once("::")
+            .map(|(owner, _)| owner)
+            .filter(|owner| self.find_in_module(module, &[owner, ""]).is_some());
+
+        if call.method {
+            return self.resolve_method(module, owner, &call.callee, call.on_self);
+        }
+
+        // Drop generic arguments, e.g. `Vec<...>::new`
+        let segments: Vec<&str> = call
+            .callee
+            .split("::")
+            .map(|segment| segment.split('<').next().unwrap_or_default())
+            .collect();
+        self.resolve_path(module, owner, &segments, true)
+    }
+
+    fn resolve_method(
+        &self,
+        module: &str,
+        owner: Option<&str>,
+        method: &str,
+        on_self: bool,
+    ) -> Option<CallNode> {
+        if let (true, Some(owner)) = (on_self, owner) {
+            if let Some(found) = self.find_in_module(module, &[owner, method]) {
+                return Some(found);
+            }
+        }
+
+        // Without the receiver type only unique methods of types known to the
+        // calling module can be resolved, this skips methods of std types
+        let uses = &self.visitor.module_map.get(module)?.uses;
+        let is_known = |other: &str, type_name: &str| {
+            other == module
+                || uses
+                    .iter()
+                    .any(|used| used.rsplit("::").next() == Some(type_name))
+        };
+        let mut candidates = self.modules().flat_map(|(other, info)| {
+            info.type_functions()
+                .filter(|(_, functions)| functions.iter().any(|f| f.name == method))
+                .filter(move |(name, _)| is_known(other, name))
+                .map(move |(name, _)| node(other, &format!("{}::{}", name, method)))
+        });
+
+        let first = candidates.next()?;
+        candidates.next().is_none().then_some(first)
+    }
+
+    fn resolve_path(
+        &self,
+        module: &str,
+        owner: Option<&str>,
+        segments: &[&str],
+        follow_uses: bool,
+    ) -> Option<CallNode> {
+        let (first, rest) = segments.split_first()?;
+        let info = self.visitor.module_map.get(module)?;
+
+        let (mut current, mut rest) = match *first {
+            "Self" => {
+                let mut path = vec![owner?];
+                path.extend(rest);
+                return self.find_in_module(module, &path);
+            }
+            "crate" => (self.visitor.root_module(module), rest),
+            "self" => (module.to_string(), rest),
+            "super" => (self.visitor.parent_module(module)?.clone(), rest),
+            _ => {
+                // Imported names, e.g. `use crate::analyzer::analyze;`
+                let used = info
+                    .uses
+                    .iter()
+                    .find(|used| used.rsplit("::").next() == Some(*first));
+                if let (true, Some(used)) = (follow_uses, used) {
+                    let mut path: Vec<&str> = used.split("::").collect();
+                    path.extend(rest);
+                    return self.resolve_path(module, owner, &path, false);
+                }
+
+                match self.visitor.submodule(module, first) {
+                    Some(submodule) => (submodule.clone(), rest),
+                    None => match self.visitor.crate_root(first) {
+                        Some(root) => (root, rest),
+                        None => (module.to_string(), segments),
+                    },
+                }
+            }
+        };
+
+        while rest.len() > 1 {
+            match self.visitor.submodule(&current, rest[0]) {
+                Some(submodule) => {
+                    current = submodule.clone();
+                    rest = &rest[1..];
+                }
+                None => break,
+            }
+        }
+
+        self.find_in_module(&current, rest)
+            .or_else(|| self.find_type_function(rest))
+    }
+
+    /// Function or method defined in a module
+    ///
+    /// An empty function name only checks that the type exists.
+    fn find_in_module(&self, module: &str, path: &[&str]) -> Option<CallNode> {
+        let info = self.visitor.module_map.get(module)?;
+        match path {
+            [function] => info
+                .functions
+                .iter()
+                .any(|f| f.name == *function)
+                .then(|| node(module, function)),
+            [type_name, function] => {
+                let found = info.type_functions().any(|(name, functions)| {
+                    name == *type_name
+                        && (function.is_empty() || functions.iter().any(|f| f.name == *function))
+                });
+                found.then(|| node(module, &format!("{}::{}", type_name, function)))
+            }
+            _ => None,
+        }
+    }
+
+    /// `Type::function` of a type imported in a way that could not be followed
+    fn find_type_function(&self, path: &[&str]) -> Option<CallNode> {
+        if path.len() != 2 {
+            return None;
+        }
+
+        let mut candidates = self
+            .modules()
+            .filter_map(|(module, _)| self.find_in_module(module, path));
+        let first = candidates.next()?;
+        candidates.next().is_none().then_some(first)
+    }
+}
@@ -0,0 +1,165 @@
+//! Conversions between types from their `From`, `TryFrom`, `Into`, `TryInto` and `AsRef` impls
+//!
+//! `impl From<A> for B` and `impl Into<B> for A` both convert `A` into `B`. Source
+//! and target types outside of the model, e.g. `String` or `std::io::Error`, are
+//! kept as written.
+use syn::__private::ToTokens;
+use syn::{GenericArgument, PathArguments, Type};
+
+use crate::module_visitor::{compact_tokens, ModulesVisitor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConversionKind {
+    From,
+    TryFrom,
+    Into,
+    TryInto,
+    AsRef,
+}
+
+impl ConversionKind {
+    fn from_trait(name: &str) -> Option<ConversionKind> {
+        match name {
+            "From" => Some(ConversionKind::From),
+            "TryFrom" => Some(ConversionKind::TryFrom),
+            "Into" => Some(ConversionKind::Into),
+            "TryInto" => Some(ConversionKind::TryInto),
+            "AsRef" => Some(ConversionKind::AsRef),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConversionKind::From => "From",
+            ConversionKind::TryFrom => "TryFrom",
+            ConversionKind::Into => "Into",
+            ConversionKind::TryInto => "TryInto",
+            ConversionKind::AsRef => "AsRef",
+        }
+    }
+
+    /// The conversion returns a `Result`
+    pub fn is_fallible(&self) -> bool {
+        matches!(self, ConversionKind::TryFrom | ConversionKind::TryInto)
+    }
+}
+
+/// Source or target of a conversion
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConversionType {
+    /// A struct or enum of the model
+    Model {
+        module: String,
+        name: String,
+        is_enum: bool,
+    },
+    /// Any other type as written, e.g. `&str`
+    External(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Conversion {
+    pub from: ConversionType,
+    pub to: ConversionType,
+    pub kind: ConversionKind,
+}
+
+/// Conversions implemented by the analyzed types, dependency modules only with `dependencies`
+pub fn conversions(visitor: &ModulesVisitor, dependencies: bool) -> Vec<Conversion> {
+    let is_shown = |module: &str| dependencies || !visitor.dependency_modules.contains(module);
+    let mut conversions = Vec::new();
+
+    for (module, info) in &visitor.module_map {
+        if !is_shown(module) {
+            continue;
+        }
+        let structs = info
+            .structs
+            .iter()
+            .map(|(name, struct_info)| (name, &struct_info.impl_traits));
+        let enums = info
+            .enums
+            .iter()
+            .map(|(name, enum_info)| (name, &enum_info.impl_traits));
+
+        for (name, impl_traits) in structs.chain(enums) {
+            // Impls of imported types belong to their definition
+            let owner = model_type(visitor, module, name)
+                .unwrap_or_else(|| ConversionType::External(name.clone()));
+
+            for impl_trait in impl_traits {
+                let Some((kind, argument)) = conversion_trait(impl_trait) else {
+                    continue;
+                };
+                let other = argument_type(visitor, module, &argument, &is_shown);
+                let (from, to) = match kind {
+                    ConversionKind::From | ConversionKind::TryFrom => (other, owner.clone()),
+                    _ => (owner.clone(), other),
+                };
+                let conversion = Conversion { from, to, kind };
+                if !conversions.contains(&conversion) {
+                    conversions.push(conversion);
+                }
+            }
+        }
+    }
+    conversions.sort();
+    conversions
+}
+
+/// Kind and type argument of a conversion trait, e.g. `From` and `Foo` for `From<Foo>`
+fn conversion_trait(impl_trait: &str) -> Option<(ConversionKind, Type)> {
+    let path = syn::parse_str::<syn::Path>(impl_trait).ok()?;
+    let last = path.segments.last()?;
+    let kind = ConversionKind::from_trait(&last.ident.to_string())?;
+    let PathArguments::AngleBracketed(arguments) = &last.arguments else {
+        return None;
+    };
+    arguments.args.iter().find_map(|argument| match argument {
+        GenericArgument::Type(ty) => Some((kind, ty.clone())),
+        _ => None,
+    })
+}
+
+/// Struct or enum of the model with the name, resolved from the module
+fn model_type(visitor: &ModulesVisitor, module: &str, path: &str) -> Option<ConversionType> {
+    let (module, name) = visitor.resolve_type(module, path)?;
+    let info = visitor.module_map.get(&module)?;
+    let is_enum = info.enums.contains_key(&name);
+    if !is_enum && info.structs.get(&name).is_none_or(|info| info.impl_only) {
+        return None;
+    }
+    Some(ConversionType::Model {
+        module,
+        name,
+        is_enum,
+    })
+}
+
+/// Model type for plain paths like `Foo` or `error::Error`, otherwise the type as written
+fn argument_type(
+    visitor: &ModulesVisitor,
+    module: &str,
+    ty: &Type,
+    is_shown: &impl Fn(&str) -> bool,
+) -> ConversionType {
+    if let Type::Path(type_path) = ty {
+        let plain = type_path.qself.is_none()
+            && type_path
+                .path
+                .segments
+                .iter()
+                .all(|segment| segment.arguments.is_none());
+        if plain {
+            let path = compact_tokens(&type_path.path.to_token_stream().to_string());
+            let model = model_type(visitor, module, &path).filter(
+                |model| matches!(model, ConversionType::Model { module, .. } if is_shown(module)),
+            );
+            if let Some(model) = model {
+                return model;
+            }
+        }
+    }
+    ConversionType::External(compact_tokens(&ty.to_token_stream().to_string()))
+}
@@ -0,0 +1,83 @@
+//! Workspace crates and their dependencies as declared in the Cargo manifests
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+/// A crate of the workspace
+#[derive(Debug, Clone, Default)]
+pub struct CrateInfo {
+    /// Package name, e.g. `cargo-diagram`
+    pub name: String,
+    /// Prefix of the crate's modules, e.g. `cargo_diagram` for `cargo_diagram__lib`
+    pub module_prefix: String,
+    pub version: Option<String>,
+    pub dependencies: Vec<CrateDependency>,
+    /// Features with the features and dependencies they enable
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CrateDependency {
+    /// Name in the manifest and its features, differs from `package` for renamed dependencies
+    pub name: String,
+    pub package: String,
+    pub kind: DependencyKind,
+    /// Version from `Cargo.lock`, the version requirement if the package is not locked
+    pub version: Option<String>,
+    pub optional: bool,
+    /// The package is a crate of the workspace
+    pub workspace: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceInfo {
+    pub crates: Vec<CrateInfo>,
+    /// Versions of the packages in `Cargo.lock`
+    pub locked_versions: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl CrateInfo {
+    /// Features enabling an optional dependency
+    ///
+    /// These are features listing `dep:name`, `name/feature` or `name`, and the implicit
+    /// feature `name` of optional dependencies never referenced with `dep:`.
+    pub fn enabling_features(&self, dependency: &str) -> Vec<String> {
+        let explicit = format!("dep:{}", dependency);
+        let sub_feature = format!("{}/", dependency);
+
+        let mut features: Vec<String> = self
+            .features
+            .iter()
+            .filter(|(_, enabled)| {
+                enabled.iter().any(|entry| {
+                    *entry == explicit || *entry == dependency || entry.starts_with(&sub_feature)
+                })
+            })
+            .map(|(feature, _)| feature.clone())
+            .collect();
+
+        let is_implicit = !self
+            .features
+            .values()
+            .flatten()
+            .any(|entry| *entry == explicit);
+        if is_implicit && !features.iter().any(|feature| feature == dependency) {
+            features.insert(0, dependency.to_string());
+        }
+        features
+    }
+}
+
+impl WorkspaceInfo {
+    /// The package is locked in several versions
+    pub fn is_duplicate(&self, package: &str) -> bool {
+        self.locked_versions
+            .get(package)
+            .is_some_and(|versions| versions.len() > 1)
+    }
+}
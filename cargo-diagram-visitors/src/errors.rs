@@ -0,0 +1,229 @@
+//! Error types with the errors they wrap and convert from
+//!
+//! A struct or enum is an error if it implements `std::error::Error`, derives
+//! `Error` (e.g. `thiserror::Error`) or its name ends with `Error`. Fields marked
+//! with `#[from]` or `#[source]` wrap their error, `From` impls between errors show
+//! where `?` propagates them.
+use syn::__private::ToTokens;
+use syn::Type;
+
+use crate::conversions::{conversions, ConversionKind, ConversionType};
+use crate::module_visitor::{compact_tokens, ErrorVariant, ModulesVisitor, STD_TYPES};
+
+#[derive(Debug, Clone)]
+pub struct ErrorType {
+    pub module: String,
+    pub name: String,
+    pub is_enum: bool,
+    /// Derives `Error`, the variants carry their messages and sources
+    pub derived: bool,
+    /// Variants of enums, the struct itself for derived structs
+    pub variants: Vec<ErrorVariant>,
+}
+
+/// How an error gets from the source into the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorEdgeKind {
+    /// A field of the variant holds the source, `from` for `#[from]`
+    Wraps {
+        variant: String,
+        field: String,
+        from: bool,
+    },
+    /// An explicit `impl From<Source> for Target`
+    From,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorEdge {
+    pub source: ConversionType,
+    pub target: ConversionType,
+    pub kind: ErrorEdgeKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorHierarchy {
+    pub errors: Vec<ErrorType>,
+    pub edges: Vec<ErrorEdge>,
+}
+
+impl ErrorHierarchy {
+    /// Error types of the analyzed crates, dependency modules only with `dependencies`
+    pub fn build(visitor: &ModulesVisitor, dependencies: bool) -> ErrorHierarchy {
+        let is_shown = |module: &str| dependencies || !visitor.dependency_modules.contains(module);
+        let mut hierarchy = ErrorHierarchy::default();
+
+        // `impl Error for Foo` may be written in another module than `Foo`
+        let mut implemented = Vec::new();
+        for (module, info) in &visitor.module_map {
+            for (name, struct_info) in &info.structs {
+                if struct_info
+                    .impl_traits
+                    .iter()
+                    .any(|path| is_error_trait(path))
+                {
+                    implemented.push(visitor.resolve_type(module, name));
+                }
+            }
+            for (name, enum_info) in &info.enums {
+                if enum_info
+                    .impl_traits
+                    .iter()
+                    .any(|path| is_error_trait(path))
+                {
+                    implemented.push(Some((module.clone(), name.clone())));
+                }
+            }
+        }
+        let is_implemented = |module: &String, name: &String| {
+            implemented
+                .iter()
+                .flatten()
+                .any(|(other, other_name)| other == module && other_name == name)
+        };
+
+        for (module, info) in &visitor.module_map {
+            if !is_shown(module) {
+                continue;
+            }
+            for (name, struct_info) in &info.structs {
+                let derived = !struct_info.error_variants.is_empty();
+                let is_error = derived || is_implemented(module, name) || name.ends_with("Error");
+                if is_error && !struct_info.impl_only && !STD_TYPES.contains(&name.as_str()) {
+                    hierarchy.errors.push(ErrorType {
+                        module: module.clone(),
+                        name: name.clone(),
+                        is_enum: false,
+                        derived,
+                        variants: struct_info.error_variants.clone(),
+                    });
+                }
+            }
+            for (name, enum_info) in &info.enums {
+                let derived = !enum_info.error_variants.is_empty();
+                if derived || is_implemented(module, name) || name.ends_with("Error") {
+                    let variants = if derived {
+                        enum_info.error_variants.clone()
+                    } else {
+                        enum_info
+                            .variants
+                            .iter()
+                            .map(|variant| ErrorVariant {
+                                name: variant.clone(),
+                                ..Default::default()
+                            })
+                            .collect()
+                    };
+                    hierarchy.errors.push(ErrorType {
+                        module: module.clone(),
+                        name: name.clone(),
+                        is_enum: true,
+                        derived,
+                        variants,
+                    });
+                }
+            }
+        }
+
+        for error in &hierarchy.errors {
+            let target = hierarchy.node(&error.module, &error.name);
+            for variant in &error.variants {
+                for source in &variant.sources {
+                    let source_type =
+                        hierarchy.source_node(visitor, &error.module, &source.source_type);
+                    hierarchy.edges.push(ErrorEdge {
+                        source: source_type,
+                        target: target.clone(),
+                        kind: ErrorEdgeKind::Wraps {
+                            variant: variant.name.clone(),
+                            field: source.field.clone(),
+                            from: source.from,
+                        },
+                    });
+                }
+            }
+        }
+
+        // Explicit conversions, sources outside of the model only if they look like errors
+        for conversion in conversions(visitor, dependencies) {
+            if conversion.kind != ConversionKind::From || !hierarchy.is_error(&conversion.to) {
+                continue;
+            }
+            let source_is_error = match &conversion.from {
+                ConversionType::External(path) => {
+                    let path = path.split('<').next().unwrap_or(path);
+                    path.rsplit("::")
+                        .next()
+                        .is_some_and(|name| name.ends_with("Error"))
+                }
+                model => hierarchy.is_error(model),
+            };
+            let wrapped = hierarchy.edges.iter().any(|edge| {
+                edge.source == conversion.from
+                    && edge.target == conversion.to
+                    && matches!(edge.kind, ErrorEdgeKind::Wraps { from: true, .. })
+            });
+            if source_is_error && !wrapped {
+                hierarchy.edges.push(ErrorEdge {
+                    source: conversion.from,
+                    target: conversion.to,
+                    kind: ErrorEdgeKind::From,
+                });
+            }
+        }
+        hierarchy
+    }
+
+    fn node(&self, module: &str, name: &str) -> ConversionType {
+        let is_enum = self
+            .errors
+            .iter()
+            .any(|error| error.module == module && error.name == name && error.is_enum);
+        ConversionType::Model {
+            module: module.to_string(),
+            name: name.to_string(),
+            is_enum,
+        }
+    }
+
+    /// Error type of the model for plain paths, otherwise the type as written
+    fn source_node(
+        &self,
+        visitor: &ModulesVisitor,
+        module: &str,
+        source_type: &str,
+    ) -> ConversionType {
+        let ty = syn::parse_str::<Type>(source_type).ok();
+        // `Box<dyn Error>` and references are shown as written
+        if let Some(Type::Path(type_path)) = &ty {
+            let path = compact_tokens(&type_path.path.to_token_stream().to_string());
+            if let Some((target, name)) = visitor.resolve_type(module, &path) {
+                let node = self.node(&target, &name);
+                if self.is_error(&node) {
+                    return node;
+                }
+            }
+        }
+        match ty {
+            Some(ty) => ConversionType::External(compact_tokens(&ty.to_token_stream().to_string())),
+            None => ConversionType::External(source_type.to_string()),
+        }
+    }
+
+    /// The node is one of the error types
+    pub fn is_error(&self, node: &ConversionType) -> bool {
+        match node {
+            ConversionType::Model { module, name, .. } => self
+                .errors
+                .iter()
+                .any(|error| &error.module == module && &error.name == name),
+            ConversionType::External(_) => false,
+        }
+    }
+}
+
+/// `std::error::Error` or `Error`
+fn is_error_trait(path: &str) -> bool {
+    let path = path.split('<').next().unwrap_or(path);
+    path == "Error" || path.ends_with("error::Error")
+}
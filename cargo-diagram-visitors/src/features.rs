@@ -0,0 +1,149 @@
+//! Cargo features of the workspace crates and what they enable
+//!
+//! Entries of the `[features]` tables are `feature`, `dep:dependency`,
+//! `dependency/feature` and `dependency?/feature`. A plain name of an optional
+//! dependency enables the dependency, like the implicit feature of the same name.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::crates::CrateInfo;
+use crate::module_visitor::ModulesVisitor;
+
+/// Something enabled by a feature
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeatureTarget {
+    Feature(String),
+    /// An optional dependency, by its name in the manifest
+    Dependency(String),
+    /// A feature of a dependency, `weak` entries (`dependency?/feature`) don't enable the dependency
+    DependencyFeature {
+        dependency: String,
+        feature: String,
+        weak: bool,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CrateFeatures {
+    pub name: String,
+    /// Declared features and implicit features of optional dependencies
+    pub features: BTreeSet<String>,
+    /// Implicit features, optional dependencies never referenced with `dep:`
+    pub implicit: BTreeSet<String>,
+    /// Dependencies enabled by features, optional or with a feature enabled
+    pub dependencies: BTreeSet<String>,
+    /// Optional dependencies by their name in the manifest
+    pub optional: BTreeSet<String>,
+    pub edges: BTreeSet<(String, FeatureTarget)>,
+    /// Gated items by feature, e.g. `module::Type` or `mod module`
+    pub gated_items: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FeatureGraph {
+    pub crates: Vec<CrateFeatures>,
+}
+
+impl FeatureGraph {
+    /// Build the feature graph of the workspace crates in `ModulesVisitor::workspace`
+    pub fn build(visitor: &ModulesVisitor) -> FeatureGraph {
+        let crates = visitor
+            .workspace
+            .crates
+            .iter()
+            .map(|crate_info| {
+                let mut features = crate_features(crate_info);
+                features.gated_items = gated_items(visitor, &crate_info.module_prefix);
+                features
+            })
+            .collect();
+        FeatureGraph { crates }
+    }
+}
+
+fn crate_features(crate_info: &CrateInfo) -> CrateFeatures {
+    let optional: BTreeSet<&str> = crate_info
+        .dependencies
+        .iter()
+        .filter(|dependency| dependency.optional)
+        .map(|dependency| dependency.name.as_str())
+        .collect();
+
+    let mut features = CrateFeatures {
+        name: crate_info.name.clone(),
+        features: crate_info.features.keys().cloned().collect(),
+        ..Default::default()
+    };
+
+    for (feature, entries) in &crate_info.features {
+        for entry in entries {
+            let target = if let Some(dependency) = entry.strip_prefix("dep:") {
+                FeatureTarget::Dependency(dependency.to_string())
+            } else if let Some((dependency, dependency_feature)) = entry.split_once('/') {
+                let weak = dependency.ends_with('?');
+                FeatureTarget::DependencyFeature {
+                    dependency: dependency.trim_end_matches('?').to_string(),
+                    feature: dependency_feature.to_string(),
+                    weak,
+                }
+            } else if !crate_info.features.contains_key(entry) && optional.contains(entry.as_str())
+            {
+                FeatureTarget::Dependency(entry.clone())
+            } else {
+                FeatureTarget::Feature(entry.clone())
+            };
+            features.edges.insert((feature.clone(), target));
+        }
+    }
+
+    features.optional = optional
+        .iter()
+        .map(|dependency| dependency.to_string())
+        .collect();
+    for dependency in optional {
+        let enabling = crate_info.enabling_features(dependency);
+        if enabling.iter().any(|feature| feature == dependency)
+            && !crate_info.features.contains_key(dependency)
+        {
+            features.features.insert(dependency.to_string());
+            features.implicit.insert(dependency.to_string());
+            features.edges.insert((
+                dependency.to_string(),
+                FeatureTarget::Dependency(dependency.to_string()),
+            ));
+        }
+    }
+
+    features.dependencies = features
+        .edges
+        .iter()
+        .filter_map(|(_, target)| match target {
+            FeatureTarget::Dependency(dependency)
+            | FeatureTarget::DependencyFeature { dependency, .. } => Some(dependency.clone()),
+            FeatureTarget::Feature(_) => None,
+        })
+        .collect();
+    features
+}
+
+/// Items gated by `#[cfg(feature = "..")]` in the modules of a crate
+fn gated_items(visitor: &ModulesVisitor, module_prefix: &str) -> BTreeMap<String, Vec<String>> {
+    let module_prefix = format!("{}__", module_prefix);
+    let mut gated: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for (module, info) in &visitor.module_map {
+        if !module.starts_with(&module_prefix) {
+            continue;
+        }
+        for gate in &info.feature_gates {
+            let item = match gate.item.strip_prefix("mod ") {
+                Some(gated_module) => format!("mod {}", visitor.module_path(gated_module)),
+                None => format!("{}::{}", visitor.module_path(module), gate.item),
+            };
+            let items = gated.entry(gate.feature.clone()).or_default();
+            if !items.contains(&item) {
+                items.push(item);
+            }
+        }
+    }
+    gated
+}
@@ -0,0 +1,326 @@
+//! Control flow of a single function, read again from its source file
+use std::fs;
+use std::path::Path;
+
+use syn::__private::ToTokens;
+use syn::visit::Visit;
+use syn::{
+    Block, Expr, ExprClosure, ExprTry, ImplItemFn, ItemFn, ItemImpl, ItemTrait, Local, Stmt,
+    TraitItemFn, Type, TypePath,
+};
+
+/// Longest text of a summarised statement
+const MAX_TEXT: usize = 60;
+
+/// A step of the control flow
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlowNode {
+    /// Consecutive statements without control flow, one line each
+    Statements(Vec<String>),
+    If {
+        condition: String,
+        then_branch: Vec<FlowNode>,
+        /// `else if` is a single nested `If`
+        else_branch: Option<Vec<FlowNode>>,
+    },
+    Match {
+        expression: String,
+        /// Pattern (with guard) and body of every arm
+        arms: Vec<(String, Vec<FlowNode>)>,
+    },
+    /// `loop`, `while ..` or `for .. in ..`
+    Loop {
+        header: String,
+        body: Vec<FlowNode>,
+    },
+    /// Early return of the `?` operator on the expression
+    Try(String),
+    Return(String),
+    Break,
+    Continue,
+}
+
+/// Read the control flow of a function from a source file
+///
+/// `function` is the function name, for methods with their type, e.g. `Type::method`.
+pub fn function_flow(file_path: &Path, function: &str) -> Option<Vec<FlowNode>> {
+    function_body(file_path, function).map(|body| block_flow(&body))
+}
+
+/// Read the body of a function from a source file, see [`function_flow`]
+pub(crate) fn function_body(file_path: &Path, function: &str) -> Option<Block> {
+    let code = fs::read_to_string(file_path).ok()?;
+    let file = syn::parse_file(&code).ok()?;
+
+    let (owner, name) = match function.rsplit_once("::") {
+        Some((owner, name)) => (owner, name),
+        None => ("", function),
+    };
+    let mut finder = FunctionFinder {
+        owner,
+        name,
+        owners: Vec::new(),
+        body: None,
+    };
+    finder.visit_file(&file);
+
+    finder.body
+}
+
+/// Finds the body of a function by its name and owning type or trait
+struct FunctionFinder<'a> {
+    owner: &'a str,
+    name: &'a str,
+    owners: Vec<String>,
+    body: Option<Block>,
+}
+
+impl FunctionFinder<'_> {
+    fn check(&mut self, name: &syn::Ident, body: &Block) {
+        let owner = self.owners.last().map(String::as_str).unwrap_or_default();
+        if self.body.is_none() && name == self.name && owner == self.owner {
+            self.body = Some(body.clone());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionFinder<'_> {
+    fn visit_item_fn(&mut self, item_fn: &'ast ItemFn) {
+        self.check(&item_fn.sig.ident, &item_fn.block);
+    }
+
+    fn visit_item_impl(&mut self, item_impl: &'ast ItemImpl) {
+        let owner = match &*item_impl.self_ty {
+            Type::Path(TypePath { path, .. }) => path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        self.owners.push(owner.unwrap_or_default());
+        syn::visit::visit_item_impl(self, item_impl);
+        self.owners.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'ast ImplItemFn) {
+        self.check(&impl_item_fn.sig.ident, &impl_item_fn.block);
+    }
+
+    fn visit_item_trait(&mut self, item_trait: &'ast ItemTrait) {
+        self.owners.push(item_trait.ident.to_string());
+        syn::visit::visit_item_trait(self, item_trait);
+        self.owners.pop();
+    }
+
+    fn visit_trait_item_fn(&mut self, trait_item_fn: &'ast TraitItemFn) {
+        if let Some(body) = &trait_item_fn.default {
+            self.check(&trait_item_fn.sig.ident, body);
+        }
+    }
+}
+
+fn block_flow(block: &Block) -> Vec<FlowNode> {
+    let mut nodes = Vec::new();
+    for stmt in &block.stmts {
+        match stmt {
+            Stmt::Local(local) => local_flow(local, &mut nodes),
+            Stmt::Expr(expr, _) => expr_flow(expr, &mut nodes),
+            Stmt::Macro(stmt_macro) => {
+                push_statement(&mut nodes, text(&stmt_macro.mac));
+            }
+            // Nested items are not part of the control flow
+            Stmt::Item(_) => {}
+        }
+    }
+    nodes
+}
+
+fn local_flow(local: &Local, nodes: &mut Vec<FlowNode>) {
+    let pattern = text(&local.pat);
+    let Some(init) = &local.init else {
+        push_statement(nodes, format!("let {}", pattern));
+        return;
+    };
+
+    if let Some((_, diverge)) = &init.diverge {
+        // `let .. else { return }` leaves on a failed match
+        let mut else_branch = Vec::new();
+        expr_flow(diverge, &mut else_branch);
+        nodes.push(FlowNode::If {
+            condition: format!("let {} = {}", pattern, text(&init.expr)),
+            then_branch: Vec::new(),
+            else_branch: Some(else_branch),
+        });
+    } else if is_control_flow(&init.expr) {
+        // `let x = if .. { } else { };` shows the branches, then the binding
+        expr_flow(&init.expr, nodes);
+        push_statement(nodes, format!("let {}", pattern));
+    } else {
+        push_statement(nodes, format!("let {} = {}", pattern, text(&init.expr)));
+    }
+
+    let mut tries = TryCollector::default();
+    tries.visit_expr(&init.expr);
+    tries.push_into(nodes);
+}
+
+fn expr_flow(expr: &Expr, nodes: &mut Vec<FlowNode>) {
+    match expr {
+        Expr::If(expr_if) => {
+            let else_branch = expr_if.else_branch.as_ref().map(|(_, else_expr)| {
+                let mut else_nodes = Vec::new();
+                match &**else_expr {
+                    Expr::Block(block) => else_nodes = block_flow(&block.block),
+                    other => expr_flow(other, &mut else_nodes),
+                }
+                else_nodes
+            });
+            nodes.push(FlowNode::If {
+                condition: text(&expr_if.cond),
+                then_branch: block_flow(&expr_if.then_branch),
+                else_branch,
+            });
+        }
+        Expr::Match(expr_match) => {
+            let arms = expr_match
+                .arms
+                .iter()
+                .map(|arm| {
+                    let mut pattern = text(&arm.pat);
+                    if let Some((_, guard)) = &arm.guard {
+                        pattern = format!("{} if {}", pattern, text(guard));
+                    }
+                    let mut body = Vec::new();
+                    expr_flow(&arm.body, &mut body);
+                    (pattern, body)
+                })
+                .collect();
+            nodes.push(FlowNode::Match {
+                expression: text(&expr_match.expr),
+                arms,
+            });
+        }
+        Expr::Loop(expr_loop) => nodes.push(FlowNode::Loop {
+            header: "loop".to_string(),
+            body: block_flow(&expr_loop.body),
+        }),
+        Expr::While(expr_while) => nodes.push(FlowNode::Loop {
+            header: format!("while {}", text(&expr_while.cond)),
+            body: block_flow(&expr_while.body),
+        }),
+        Expr::ForLoop(expr_for) => nodes.push(FlowNode::Loop {
+            header: format!("for {} in {}", text(&expr_for.pat), text(&expr_for.expr)),
+            body: block_flow(&expr_for.body),
+        }),
+        Expr::Block(expr_block) => nodes.extend(block_flow(&expr_block.block)),
+        Expr::Unsafe(expr_unsafe) => nodes.extend(block_flow(&expr_unsafe.block)),
+        Expr::Return(expr_return) => {
+            let mut tries = TryCollector::default();
+            tries.visit_expr_return(expr_return);
+            tries.push_into(nodes);
+            nodes.push(FlowNode::Return(
+                expr_return.expr.as_ref().map(text).unwrap_or_default(),
+            ));
+        }
+        Expr::Break(_) => nodes.push(FlowNode::Break),
+        Expr::Continue(_) => nodes.push(FlowNode::Continue),
+        other => {
+            push_statement(nodes, text(other));
+            let mut tries = TryCollector::default();
+            tries.visit_expr(other);
+            tries.push_into(nodes);
+        }
+    }
+}
+
+fn is_control_flow(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::If(_)
+            | Expr::Match(_)
+            | Expr::Loop(_)
+            | Expr::While(_)
+            | Expr::ForLoop(_)
+            | Expr::Block(_)
+            | Expr::Unsafe(_)
+    )
+}
+
+/// Add a statement, merged with the previous statements
+fn push_statement(nodes: &mut Vec<FlowNode>, statement: String) {
+    match nodes.last_mut() {
+        Some(FlowNode::Statements(statements)) => statements.push(statement),
+        _ => nodes.push(FlowNode::Statements(vec![statement])),
+    }
+}
+
+/// Collects the operands of the `?` operators of a statement
+#[derive(Default)]
+struct TryCollector(Vec<String>);
+
+impl TryCollector {
+    fn push_into(self, nodes: &mut Vec<FlowNode>) {
+        nodes.extend(self.0.into_iter().map(FlowNode::Try));
+    }
+}
+
+impl<'ast> Visit<'ast> for TryCollector {
+    fn visit_expr_try(&mut self, expr_try: &'ast ExprTry) {
+        syn::visit::visit_expr_try(self, expr_try);
+        self.0.push(text(&expr_try.expr));
+    }
+
+    // `?` inside of closures returns from the closure
+    fn visit_expr_closure(&mut self, _: &'ast ExprClosure) {}
+}
+
+/// Source text of a syntax node, shortened to a summary
+pub(crate) fn text<T: ToTokens>(node: &T) -> String {
+    let mut text = node.to_token_stream().to_string();
+    // Remove the spaces of the token stream where rustfmt would not put any
+    for (from, to) in [
+        (" ! (", "!("),
+        (" ! [", "!["),
+        (" ! {", "!{"),
+        (" :: ", "::"),
+        (" (", "("),
+        ("( ", "("),
+        (" )", ")"),
+        (" .", "."),
+        (". ", "."),
+        (" ,", ","),
+        (" ?", "?"),
+        (" [", "["),
+        ("[ ", "["),
+        (" ]", "]"),
+    ] {
+        text = text.replace(from, to);
+    }
+
+    text = tidy_references(&text);
+    text = text.trim_end_matches(';').to_string();
+
+    if text.chars().count() > MAX_TEXT {
+        text = text.chars().take(MAX_TEXT - 3).collect::<String>() + "...";
+    }
+    text
+}
+
+/// Drop the space after the `&` of references, `a & b` and `a && b` keep it
+fn tidy_references(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tidy = String::with_capacity(text.len());
+    for (i, c) in chars.iter().enumerate() {
+        if *c == ' ' && i > 0 && chars[i - 1] == '&' {
+            let before = chars[..i - 1].iter().rev().find(|c| **c != ' ');
+            let after_operand = before.is_some_and(|before| {
+                before.is_alphanumeric() || matches!(before, ')' | ']' | '&' | '_' | '"')
+            });
+            if !after_operand {
+                continue;
+            }
+        }
+        tidy.push(*c);
+    }
+    tidy
+}
@@ -0,0 +1,338 @@
+//! Minimal JSON reader for rustdoc output
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// Numbers are kept as written
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    /// Get a field of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(values) => values,
+            _ => &[],
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, Json>> {
+        match self {
+            Json::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    /// Strings and numbers as text, e.g. for ids
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            Json::String(value) | Json::Number(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document
+pub fn parse(input: &str) -> Result<Json, String> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = BTreeMap::new();
+                if self.eat(b'}') {
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.insert(key, self.value()?);
+                    if !self.eat(b',') {
+                        self.expect(b'}')?;
+                        return Ok(Json::Object(fields));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                if self.eat(b']') {
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    if !self.eat(b',') {
+                        self.expect(b']')?;
+                        return Ok(Json::Array(values));
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.' | b'e' | b'E'))
+                {
+                    self.pos += 1;
+                }
+                Ok(Json::Number(self.text(start, self.pos)))
+            }
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.eat(b'"') {
+            return Err(self.error("expected string"));
+        }
+
+        let mut value = String::new();
+        let mut start = self.pos;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    value.push_str(&self.text(start, self.pos));
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some(b'\\') => {
+                    value.push_str(&self.text(start, self.pos));
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("invalid escape"))?;
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => value.push('\n'),
+                        b't' => value.push('\t'),
+                        b'r' => value.push('\r'),
+                        b'b' => value.push('\u{8}'),
+                        b'f' => value.push('\u{c}'),
+                        b'u' => value.push(self.unicode()?),
+                        other => value.push(other as char),
+                    }
+                    start = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn unicode(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        // Surrogate pair
+        if !self.bytes[self.pos..].starts_with(b"\\u") {
+            return Err(self.error("invalid surrogate pair"));
+        }
+        self.pos += 2;
+        let low = self.hex()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("invalid surrogate pair"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    /// Exactly four hex digits, `from_str_radix` alone would accept `+` and fewer digits
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let value = u32::from_str_radix(&String::from_utf8_lossy(digits), 16)
+            .map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        if self.bytes[self.pos..].starts_with(keyword.as_bytes()) {
+            self.pos += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("unknown keyword"))
+        }
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        String::from_utf8_lossy(&self.bytes[start..end]).into_owned()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("offset {}: {}", self.pos, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+
+    #[test]
+    fn escapes() {
+        let json = parse(r#""quote \" slash \/ backslash \\ \n\t\r\b\f \u00e9""#).unwrap();
+        assert_eq!(
+            json,
+            string("quote \" slash / backslash \\ \n\t\r\u{8}\u{c} é")
+        );
+        // Multi-byte characters are copied unchanged
+        assert_eq!(parse(r#""grüße""#).unwrap(), string("grüße"));
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(parse(r#""\ud83d\ude00""#).unwrap(), string("😀"));
+        // A high surrogate needs a low surrogate
+        assert!(parse(r#""\ud83d""#).is_err());
+        assert!(parse(r#""\ud83d\u0041""#).is_err());
+        // A low surrogate on its own
+        assert!(parse(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn truncated_unicode_escapes() {
+        assert!(parse(r#""\u12""#).is_err());
+        assert!(parse(r#""\u12"#).is_err());
+        assert!(parse(r#""\u"#).is_err());
+        assert!(parse(r#""\ud83d\ude"#).is_err());
+        assert!(parse(r#""\u+123""#).is_err());
+    }
+
+    #[test]
+    fn nested_arrays_and_objects() {
+        let json = parse(
+            r#"{
+                "index": {"0:1": {"name": "Foo", "inner": {"struct": {"fields": [1, 2]}}}},
+                "paths": [[], [null, true, false], {}],
+                "format_version": 30,
+                "ratio": -1.5e3
+            }"#,
+        )
+        .unwrap();
+
+        let item = json
+            .get("index")
+            .and_then(|index| index.get("0:1"))
+            .unwrap();
+        assert_eq!(item.get("name").and_then(Json::as_str), Some("Foo"));
+        let fields = item
+            .get("inner")
+            .and_then(|inner| inner.get("struct"))
+            .and_then(|inner| inner.get("fields"))
+            .unwrap();
+        assert_eq!(
+            fields.as_array(),
+            [Json::Number("1".to_string()), Json::Number("2".to_string())]
+        );
+
+        let paths = json.get("paths").unwrap().as_array();
+        assert_eq!(paths[0], Json::Array(Vec::new()));
+        assert_eq!(
+            paths[1],
+            Json::Array(vec![Json::Null, Json::Bool(true), Json::Bool(false)])
+        );
+        assert_eq!(paths[2], Json::Object(BTreeMap::new()));
+        assert_eq!(
+            json.get("format_version").and_then(Json::to_text),
+            Some("30".to_string())
+        );
+        assert_eq!(json.get("ratio"), Some(&Json::Number("-1.5e3".to_string())));
+    }
+
+    #[test]
+    fn invalid_documents() {
+        assert!(parse("").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse(r#"{"a" 1}"#).is_err());
+        assert!(parse(r#"{"a": 1,}"#).is_err());
+        assert!(parse("[1] 2").is_err());
+        assert!(parse("nul").is_err());
+        assert!(parse(r#""unterminated"#).is_err());
+    }
+}
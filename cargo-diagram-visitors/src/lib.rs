@@ -2,49 +2,122 @@
 use std::fs;
 use std::path::{Path as StdPath, PathBuf};
 use syn::visit::Visit;
-use tracing::trace;
+use tracing::{debug, trace};
 
+use cache::cache_key;
 use module_visitor::ModulesVisitor;
 
+pub mod cache;
+pub mod call_graph;
+pub mod conversions;
+pub mod crates;
+pub mod errors;
+pub mod features;
+pub mod flow;
+mod json;
+pub mod module_graph;
 pub mod module_visitor;
+pub mod proc_macros;
+pub mod relations;
+pub mod rustdoc;
+pub mod sequence;
+pub mod state_machine;
+pub mod trait_matrix;
 
-/// Parse a rust file
-fn parse_rust_file(file_path: &StdPath) -> syn::File {
-    let code = fs::read_to_string(file_path).expect("Konnte die Datei nicht lesen");
-
-    syn::parse_file(&code).expect("Fehler beim Parsen des Quellcodes")
+/// Parse rust code
+fn parse_rust_code(code: &str) -> syn::File {
+    syn::parse_file(code).expect("Fehler beim Parsen des Quellcodes")
 }
 
 /// Analyze a file and add content to visitor
+///
+/// With a `cache_dir` the extraction results of unchanged files are reused.
 pub fn analyze_file(
     file_path: &StdPath,
     base_dir: &StdPath,
     module_name: &str,
     visitor: &mut ModulesVisitor,
+    cache_dir: Option<&StdPath>,
 ) {
-    let syntax_tree = parse_rust_file(file_path);
+    let code = fs::read_to_string(file_path).expect("Konnte die Datei nicht lesen");
+    let key = cache_key(module_name, &code);
 
-    visitor.current_module = module_name.to_string();
+    let cached = cache_dir.and_then(|dir| cache::load(dir, module_name, &key));
+    let mut modules = match cached {
+        Some(modules) => {
+            trace!("Cache-Treffer: {:?}", file_path);
+            modules
+        }
+        None => {
+            let mut file_visitor = ModulesVisitor {
+                current_module: module_name.to_string(),
+                ..Default::default()
+            };
+            file_visitor.visit_file(&parse_rust_code(&code));
+
+            if let Some(dir) = cache_dir {
+                if let Err(err) = cache::store(dir, module_name, &key, &file_visitor.module_map) {
+                    debug!("Cache konnte nicht geschrieben werden: {}", err);
+                }
+            }
+            file_visitor.module_map
+        }
+    };
 
-    visitor.visit_file(&syntax_tree);
+    // The path is not part of the cache key, the same file may be cached for another path
+    for info in modules.values_mut() {
+        info.source_file = file_path.display().to_string();
+    }
 
-    let submodules: Vec<String> = visitor
-        .module_map
+    // Only follow the submodules declared in this file, other files may use the same module name
+    let submodules: Vec<String> = modules
         .get(module_name)
         .map(|info| info.submodules.clone())
         .unwrap_or_default();
 
+    visitor.current_module = module_name.to_string();
+    visitor.merge(modules);
+
     for submodule in submodules {
-        let module_path = find_module_path(&submodule, base_dir);
+        let file_name = submodule.rsplit("__").next().unwrap_or(&submodule);
+        let module_path = find_module_path(file_name, base_dir);
         if let Some(mod_path) = module_path {
             trace!("Analysiere Modul: {:?}", mod_path);
-            analyze_file(&mod_path, mod_path.parent().unwrap(), &submodule, visitor);
+            analyze_file(
+                &mod_path,
+                mod_path.parent().unwrap(),
+                &submodule,
+                visitor,
+                cache_dir,
+            );
         } else {
             trace!("Modul {:?} nicht gefunden!", submodule);
         }
     }
 }
 
+/// Analyze a macro expanded file, e.g. saved from `cargo rustc -- -Zunpretty=expanded`
+///
+/// Expanded sources contain all modules inline, so no other files are read. The
+/// modules of the crate in `visitor` are replaced and items missing in the regular
+/// sources are marked as macro generated.
+pub fn analyze_expanded_file(file_path: &StdPath, module_name: &str, visitor: &mut ModulesVisitor) {
+    let code = fs::read_to_string(file_path).expect("Konnte die Datei nicht lesen");
+
+    let mut expanded_visitor = ModulesVisitor {
+        current_module: module_name.to_string(),
+        ..Default::default()
+    };
+    expanded_visitor.visit_file(&parse_rust_code(&code));
+    for info in expanded_visitor.module_map.values_mut() {
+        info.move_enum_impls();
+        info.source_file = file_path.display().to_string();
+    }
+    expanded_visitor.mark_macro_generated(visitor);
+
+    visitor.module_map.extend(expanded_visitor.module_map);
+}
+
 /// Get module path
 fn find_module_path(module: &str, base_dir: &StdPath) -> Option<PathBuf> {
     let mod_file = base_dir.join(format!("{}.rs", module));
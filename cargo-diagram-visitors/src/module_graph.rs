@@ -0,0 +1,190 @@
+//! Dependencies between modules from `use` statements and path references
+//!
+//! Every `use` and every path with several segments counts as one reference of the
+//! module it resolves to. Paths starting with an imported name are resolved through
+//! the `use` statement first, e.g. `fs::read` after `use std::fs;`.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::module_visitor::{expand_import, ModulesVisitor};
+
+/// Primitive types with associated items, e.g. `u32::MAX` or `str::from_utf8`
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64",
+];
+
+/// A module depending on another module or crate
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleEdge {
+    pub from: String,
+    /// A module, or the name of a crate that was not analyzed or is collapsed
+    pub to: String,
+    /// The edge leaves the crate of `from`
+    pub external: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModuleGraph {
+    /// Analyzed modules
+    pub modules: BTreeSet<String>,
+    /// Crates without analyzed modules in the graph, e.g. `std` or `serde`
+    pub crates: BTreeSet<String>,
+    /// Edges with the number of references
+    pub edges: BTreeMap<ModuleEdge, usize>,
+    /// Edges on a dependency cycle
+    pub cycles: BTreeSet<ModuleEdge>,
+}
+
+impl ModuleGraph {
+    /// Build the module graph of all analyzed modules
+    ///
+    /// Modules of dependency crates are collapsed to their crate unless `dependencies` is set.
+    pub fn build(visitor: &ModulesVisitor, dependencies: bool) -> ModuleGraph {
+        let mut graph = ModuleGraph::default();
+        let is_shown = |module: &str| dependencies || !visitor.dependency_modules.contains(module);
+
+        for (module, info) in &visitor.module_map {
+            if !is_shown(module) {
+                continue;
+            }
+            graph.modules.insert(module.clone());
+
+            let references = info.uses.iter().map(|used| used.to_string()).chain(
+                info.path_references
+                    .iter()
+                    .map(|path| expand_import(&info.uses, path)),
+            );
+            for path in references {
+                let to = match visitor.resolve_module_path(module, &path) {
+                    Some(target) if is_shown(&target) => target,
+                    Some(target) => crate_name(&visitor.root_module(&target)),
+                    None => match external_crate(visitor, module, &path) {
+                        Some(crate_name) => crate_name,
+                        None => continue,
+                    },
+                };
+                if to == *module {
+                    continue;
+                }
+
+                let external = !visitor.module_map.contains_key(&to)
+                    || visitor.root_module(&to) != visitor.root_module(module);
+                if !visitor.module_map.contains_key(&to) {
+                    graph.crates.insert(to.clone());
+                }
+                *graph
+                    .edges
+                    .entry(ModuleEdge {
+                        from: module.clone(),
+                        to,
+                        external,
+                    })
+                    .or_default() += 1;
+            }
+        }
+
+        graph.cycles = graph.cycle_edges();
+        graph
+    }
+
+    /// Edges between the modules of a strongly connected component (Tarjan)
+    fn cycle_edges(&self) -> BTreeSet<ModuleEdge> {
+        let mut successors: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in self.edges.keys() {
+            successors.entry(&edge.from).or_default().push(&edge.to);
+        }
+
+        let mut tarjan = Tarjan {
+            successors: &successors,
+            index: BTreeMap::new(),
+            low_link: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            component: BTreeMap::new(),
+            components: 0,
+        };
+        for module in &self.modules {
+            if !tarjan.index.contains_key(module.as_str()) {
+                tarjan.connect(module);
+            }
+        }
+
+        self.edges
+            .keys()
+            .filter(|edge| {
+                let from = tarjan.component.get(edge.from.as_str());
+                from.is_some() && from == tarjan.component.get(edge.to.as_str())
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Crate of an unresolved path, types like `Vec::new` or `u32::MAX` are skipped
+fn external_crate(visitor: &ModulesVisitor, module: &str, path: &str) -> Option<String> {
+    let (first, _) = path.split_once("::")?;
+    let is_crate_name = first
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_lowercase() || c == '_')
+        && !matches!(first, "crate" | "self" | "super")
+        && !PRIMITIVES.contains(&first);
+    (is_crate_name && visitor.submodule(module, first).is_none()).then(|| first.to_string())
+}
+
+/// Crate name of a root module, e.g. `serde` for `serde__lib`
+fn crate_name(root: &str) -> String {
+    root.strip_suffix("__lib")
+        .or_else(|| root.strip_suffix("__main"))
+        .unwrap_or(root)
+        .to_string()
+}
+
+struct Tarjan<'a> {
+    successors: &'a BTreeMap<&'a str, Vec<&'a str>>,
+    index: BTreeMap<&'a str, usize>,
+    low_link: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    /// Component of the modules in components with more than one module
+    component: BTreeMap<&'a str, usize>,
+    components: usize,
+}
+
+impl<'a> Tarjan<'a> {
+    fn connect(&mut self, module: &'a str) {
+        let index = self.index.len();
+        self.index.insert(module, index);
+        self.low_link.insert(module, index);
+        self.stack.push(module);
+        self.on_stack.insert(module);
+
+        for &next in self.successors.get(module).into_iter().flatten() {
+            if !self.index.contains_key(next) {
+                self.connect(next);
+                let low_link = self.low_link[module].min(self.low_link[next]);
+                self.low_link.insert(module, low_link);
+            } else if self.on_stack.contains(next) {
+                let low_link = self.low_link[module].min(self.index[next]);
+                self.low_link.insert(module, low_link);
+            }
+        }
+
+        if self.low_link[module] == index {
+            let mut members = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                members.push(member);
+                if member == module {
+                    break;
+                }
+            }
+            if members.len() > 1 {
+                for member in members {
+                    self.component.insert(member, self.components);
+                }
+                self.components += 1;
+            }
+        }
+    }
+}
@@ -1,13 +1,17 @@
-use std::collections::BTreeMap;
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::{BTreeMap, BTreeSet};
 use syn::__private::ToTokens;
 use syn::parse::Parse;
 use syn::visit::Visit;
 use syn::{
-    Attribute, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStruct, ItemTrait, Lit, Meta, PatIdent,
-    PatType, PathArguments, ReturnType, TraitItem, TraitItemFn, Type, TypePath,
+    Attribute, Expr, ExprCall, ExprMethodCall, ExprPath, ImplItemFn, ItemEnum, ItemFn, ItemImpl,
+    ItemMacro, ItemMod, ItemStruct, ItemTrait, ItemUse, Lit, Macro, Meta, PatIdent, PatType,
+    ReturnType, TraitItem, TraitItemFn, Type, TypeParamBound, TypePath, UseTree,
 };
 
-#[derive(Debug, Clone)]
+use crate::crates::WorkspaceInfo;
+
+#[derive(Debug, Clone, Default)]
 pub struct ModuleInfo {
     pub structs: BTreeMap<String, StructInfo>,
     pub enums: BTreeMap<String, EnumInfo>,
@@ -15,116 +19,661 @@ pub struct ModuleInfo {
     pub submodules: Vec<String>,
     pub functions: Vec<FunctionInfo>,
     pub description: String,
+    pub macros: Vec<MacroInfo>,
+    pub macro_invocations: Vec<MacroInvocation>,
+    /// Paths imported with `use`, e.g. `serde::Serialize`
+    pub uses: Vec<String>,
+    /// Paths with several segments in signatures and bodies, once per occurrence,
+    /// e.g. `crate::analyzer::analyze` or `std::fs::File`
+    pub path_references: Vec<String>,
+    /// Derive and attribute macros applied to items of the module
+    pub attribute_usages: Vec<AttributeUsage>,
+    /// Items only compiled with a cargo feature, from `#[cfg(feature = "..")]`
+    pub feature_gates: Vec<FeatureGate>,
+    /// Calls made in the function bodies of the module
+    pub calls: Vec<CallInfo>,
+    /// Trait impls for all types satisfying some bounds, e.g. `impl<T: Display> Trait for T`
+    pub blanket_impls: Vec<BlanketImpl>,
+    /// File the module was read from, used to read function bodies again
+    pub source_file: String,
+}
+
+/// An item gated by a cargo feature
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureGate {
+    pub feature: String,
+    /// The gated item named like in `AttributeUsage::item`, e.g. `Type::method` or `mod module`
+    pub item: String,
+}
+
+/// A trait implemented for a generic parameter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlanketImpl {
+    pub trait_name: String,
+    /// Trait bounds of the parameter, from the generics and the where clause
+    pub bounds: Vec<String>,
+}
+
+/// A `macro_rules!` definition
+#[derive(Debug, Clone, Default)]
+pub struct MacroInfo {
+    pub name: String,
+    /// Marked with `#[macro_export]`
+    pub exported: bool,
+    pub arms: usize,
+    pub description: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MacroInvocation {
+    /// Path of the macro as written, e.g. `tracing::debug`
+    pub name: String,
+    /// Function the macro is invoked in, e.g. `Type::method`, empty at item level
+    pub context: String,
+}
+
+/// A call or function reference in a function body, resolved by the call graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallInfo {
+    /// Calling function, e.g. `Type::method` or `function`
+    pub caller: String,
+    /// Path of the called function as written, or the method name for method calls
+    pub callee: String,
+    /// Method call syntax, e.g. `value.method()`
+    pub method: bool,
+    /// Method called on `self`
+    pub on_self: bool,
+}
+
+/// A derive or attribute macro applied to an item, e.g. `#[derive(Serialize)]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttributeUsage {
+    /// Path of the macro as written, e.g. `tokio::main`
+    pub path: String,
+    /// Used inside of `#[derive(..)]`
+    pub derive: bool,
+    /// The annotated item, e.g. `Type`, `Type::method` or `impl Trait for Type`
+    pub item: String,
+    /// Type the item belongs to, empty for functions and modules
+    pub owner: String,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct TraitInfo {
     pub name: String,
     pub functions: Vec<FunctionInfo>,
+    /// Traits required by this trait, e.g. `Debug` for `trait Error: Debug`
+    pub supertraits: Vec<String>,
     pub description: String,
+    /// Only present in macro expanded sources
+    pub macro_generated: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct StructInfo {
     pub derives: Vec<String>,
     pub impl_traits: Vec<String>,
     pub functions: Vec<FunctionInfo>,
     pub members: Vec<MemberInfo>,
     pub description: String,
+    /// Only present in macro expanded sources
+    pub macro_generated: bool,
+    /// Only known from impls, e.g. `impl From<A> for other::B` or `impl Trait for String`
+    pub impl_only: bool,
+    /// Message and sources of an error deriving `Error`, named after the struct
+    pub error_variants: Vec<ErrorVariant>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct EnumInfo {
     pub variants: Vec<String>,
     pub derives: Vec<String>,
     pub impl_traits: Vec<String>,
+    pub functions: Vec<FunctionInfo>,
     pub description: String,
+    /// Only present in macro expanded sources
+    pub macro_generated: bool,
+    /// Messages and sources of the variants of an error deriving `Error`
+    pub error_variants: Vec<ErrorVariant>,
 }
 
-#[derive(Debug, Clone)]
+/// A variant of an error from its `thiserror` attributes
+#[derive(Debug, Clone, Default)]
+pub struct ErrorVariant {
+    pub name: String,
+    /// Format string of `#[error("...")]`, `transparent` for `#[error(transparent)]`
+    pub message: String,
+    pub sources: Vec<ErrorSource>,
+}
+
+/// A field wrapping another error, marked with `#[from]` or `#[source]` or named `source`
+#[derive(Debug, Clone, Default)]
+pub struct ErrorSource {
+    /// Name of the field, the index for tuple fields
+    pub field: String,
+    pub source_type: String,
+    /// `#[from]` also implements `From` for the source
+    pub from: bool,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct FunctionInfo {
     pub name: String,
     pub parameters: Vec<ParameterInfo>,
     pub public: bool,
     pub return_type: String,
     pub description: String,
+    /// Only present in macro expanded sources
+    pub macro_generated: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ParameterInfo {
     pub name: String,
     pub param_type: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MemberInfo {
     pub name: String,
     pub member_type: String,
 }
 
+#[derive(Default)]
 pub struct ModulesVisitor {
     pub module_map: BTreeMap<String, ModuleInfo>,
     pub current_module: String,
+    /// Modules that belong to analyzed dependency crates
+    pub dependency_modules: BTreeSet<String>,
+    /// Dependencies of the workspace crates by module prefix, e.g. `my_crate` -> `serde`
+    ///
+    /// Workspace crates are named by their module prefix, other crates by their crate name.
+    pub crate_dependencies: BTreeMap<String, BTreeSet<String>>,
+    /// Workspace crates read from the Cargo manifests and `Cargo.lock`
+    pub workspace: WorkspaceInfo,
+    /// Names of the enclosing impl or trait and function while visiting bodies
+    pub(crate) function_context: Vec<String>,
+}
+
+impl StructInfo {
+    /// Entry of a type seen in an impl before or without its definition
+    fn impl_only() -> StructInfo {
+        StructInfo {
+            impl_only: true,
+            ..Default::default()
+        }
+    }
+}
+
+impl ModuleInfo {
+    /// The module defines a struct, enum or trait of the name
+    pub fn defines_type(&self, name: &str) -> bool {
+        self.enums.contains_key(name)
+            || self.traits.iter().any(|info| info.name == name)
+            || self.structs.get(name).is_some_and(|info| !info.impl_only)
+    }
+
+    /// Methods of the structs, enums and traits by type name
+    pub fn type_functions(&self) -> impl Iterator<Item = (&str, &[FunctionInfo])> {
+        let structs = self
+            .structs
+            .iter()
+            .map(|(name, info)| (name.as_str(), info.functions.as_slice()));
+        let enums = self
+            .enums
+            .iter()
+            .map(|(name, info)| (name.as_str(), info.functions.as_slice()));
+        let traits = self
+            .traits
+            .iter()
+            .map(|info| (info.name.as_str(), info.functions.as_slice()));
+        structs.chain(enums).chain(traits)
+    }
+
+    /// Move impls of enums into their `EnumInfo`
+    ///
+    /// While visiting, impls are collected as structs because the enum may be
+    /// defined after its impls.
+    pub fn move_enum_impls(&mut self) {
+        let enum_impls: Vec<String> = self
+            .structs
+            .keys()
+            .filter(|name| self.enums.contains_key(*name))
+            .cloned()
+            .collect();
+
+        for name in enum_impls {
+            let (Some(impls), Some(enum_info)) =
+                (self.structs.remove(&name), self.enums.get_mut(&name))
+            else {
+                continue;
+            };
+            for derive in impls.derives {
+                if !enum_info.derives.contains(&derive) {
+                    enum_info.derives.push(derive);
+                }
+            }
+            enum_info.impl_traits.extend(impls.impl_traits);
+            enum_info.functions.extend(impls.functions);
+        }
+    }
+}
+
+impl ModulesVisitor {
+    /// Merge modules extracted from a single file into this visitor
+    pub fn merge(&mut self, modules: BTreeMap<String, ModuleInfo>) {
+        for (module, info) in modules {
+            let module_info = self.module_map.entry(module).or_default();
+
+            for (struct_name, struct_info) in info.structs {
+                let existing = module_info
+                    .structs
+                    .entry(struct_name)
+                    .or_insert_with(StructInfo::impl_only);
+                existing.impl_only &= struct_info.impl_only;
+                existing.derives.extend(struct_info.derives);
+                existing.impl_traits.extend(struct_info.impl_traits);
+                existing.functions.extend(struct_info.functions);
+                existing.members.extend(struct_info.members);
+                existing.error_variants.extend(struct_info.error_variants);
+                if existing.description.is_empty() {
+                    existing.description = struct_info.description;
+                }
+                existing.macro_generated |= struct_info.macro_generated;
+            }
+            module_info.enums.extend(info.enums);
+            module_info.move_enum_impls();
+            module_info.traits.extend(info.traits);
+            module_info.submodules.extend(info.submodules);
+            module_info.functions.extend(info.functions);
+            module_info.macros.extend(info.macros);
+            module_info.macro_invocations.extend(info.macro_invocations);
+            module_info.uses.extend(info.uses);
+            module_info.path_references.extend(info.path_references);
+            module_info.attribute_usages.extend(info.attribute_usages);
+            module_info.feature_gates.extend(info.feature_gates);
+            module_info.calls.extend(info.calls);
+            module_info.blanket_impls.extend(info.blanket_impls);
+            if !info.description.is_empty() {
+                module_info.description = info.description;
+            }
+            if !info.source_file.is_empty() {
+                module_info.source_file = info.source_file;
+            }
+        }
+    }
+
+    /// Mark items that are missing in the analysis of the regular sources as macro generated
+    ///
+    /// `self` holds the analysis of the macro expanded sources.
+    pub fn mark_macro_generated(&mut self, source: &ModulesVisitor) {
+        for (module, info) in &mut self.module_map {
+            let source_info = source.module_map.get(module);
+
+            for (struct_name, struct_info) in &mut info.structs {
+                let source_struct = source_info.and_then(|source| source.structs.get(struct_name));
+                struct_info.macro_generated = source_struct.is_none();
+                mark_functions(
+                    &mut struct_info.functions,
+                    source_struct.map(|source| source.functions.as_slice()),
+                );
+            }
+            for (enum_name, enum_info) in &mut info.enums {
+                let source_enum = source_info.and_then(|source| source.enums.get(enum_name));
+                enum_info.macro_generated = source_enum.is_none();
+                mark_functions(
+                    &mut enum_info.functions,
+                    source_enum.map(|source| source.functions.as_slice()),
+                );
+            }
+            for trait_info in &mut info.traits {
+                trait_info.macro_generated = source_info.is_none_or(|source| {
+                    !source
+                        .traits
+                        .iter()
+                        .any(|other| other.name == trait_info.name)
+                });
+            }
+            mark_functions(
+                &mut info.functions,
+                source_info.map(|source| source.functions.as_slice()),
+            );
+        }
+    }
+}
+
+impl ModulesVisitor {
+    /// Record the derive and attribute macros and the feature gates of an item
+    fn record_attributes(&mut self, attrs: &[Attribute], item: &str, owner: &str) {
+        let module_info = self
+            .module_map
+            .entry(self.current_module.clone())
+            .or_default();
+
+        module_info
+            .feature_gates
+            .extend(
+                extract_cfg_features(attrs)
+                    .into_iter()
+                    .map(|feature| FeatureGate {
+                        feature,
+                        item: item.to_string(),
+                    }),
+            );
+
+        let usages = &mut module_info.attribute_usages;
+
+        let derives = extract_derives(attrs);
+        let attributes = extract_macro_attributes(attrs, &derives)
+            .into_iter()
+            .map(|path| (path, false));
+        let derives = derives.into_iter().map(|path| (path, true));
+        for (path, derive) in derives.chain(attributes) {
+            usages.push(AttributeUsage {
+                path,
+                derive,
+                item: item.to_string(),
+                owner: owner.to_string(),
+            });
+        }
+    }
+}
+
+impl ModulesVisitor {
+    /// Module that declares the given module as submodule
+    pub fn parent_module(&self, module: &str) -> Option<&String> {
+        self.module_map
+            .iter()
+            .find(|(_, info)| info.submodules.iter().any(|sub| sub == module))
+            .map(|(parent, _)| parent)
+    }
+
+    /// Root module (`<crate>__lib` or `<crate>__main`) of a module
+    pub fn root_module(&self, module: &str) -> String {
+        let mut current = module.to_string();
+        // Guard against cycles of same-named modules
+        for _ in 0..self.module_map.len() {
+            match self.parent_module(&current) {
+                Some(parent) if parent != module => current = parent.clone(),
+                _ => break,
+            }
+        }
+        current
+    }
+
+    /// Submodule declared by a module, see `submodule_key`
+    pub fn submodule(&self, module: &str, name: &str) -> Option<&String> {
+        let dependency_name = format!("__{}", name);
+        self.module_map
+            .get(module)?
+            .submodules
+            .iter()
+            .find(|sub| *sub == name || sub.ends_with(&dependency_name))
+            .filter(|sub| self.module_map.contains_key(*sub))
+    }
+
+    /// Root module of a crate, e.g. `my_crate__lib` for `my_crate`
+    pub fn crate_root(&self, crate_name: &str) -> Option<String> {
+        ["__lib", "__main"]
+            .iter()
+            .map(|suffix| format!("{}{}", crate_name, suffix))
+            .find(|root| self.module_map.contains_key(root))
+    }
+
+    /// Module a path used in a module points to, e.g. `crate::analyzer::analyze` to `analyzer`
+    ///
+    /// The deepest module of the path is returned, items at its end are ignored.
+    /// Paths into crates that were not analyzed are not resolved.
+    pub fn resolve_module_path(&self, module: &str, path: &str) -> Option<String> {
+        let mut segments = path.split("::");
+        let mut current = match segments.next()? {
+            "crate" => self.root_module(module),
+            "self" => module.to_string(),
+            "super" => self.parent_module(module)?.clone(),
+            first => match self.submodule(module, first) {
+                Some(submodule) => submodule.clone(),
+                None => self.crate_root(first)?,
+            },
+        };
+
+        for segment in segments {
+            match segment {
+                "super" => current = self.parent_module(&current)?.clone(),
+                _ => match self.submodule(&current, segment) {
+                    Some(submodule) => current = submodule.clone(),
+                    None => break,
+                },
+            }
+        }
+        Some(current)
+    }
+
+    /// Module and name of the struct, enum or trait a type path used in a module points to
+    ///
+    /// Paths are resolved through the modules and `use` statements, including glob
+    /// imports and re-exports. Names that are neither defined nor imported in the module
+    /// fall back to the only type of the name, as items may come from unresolved globs.
+    pub fn resolve_type(&self, module: &str, path: &str) -> Option<(String, String)> {
+        self.resolve_type_in(module, path, 0).or_else(|| {
+            let imported = self
+                .module_map
+                .get(module)?
+                .uses
+                .iter()
+                .any(|used| used.rsplit("::").next() == Some(path));
+            if path.contains("::") || imported {
+                return None;
+            }
+            let mut definitions = self
+                .module_map
+                .iter()
+                .filter(|(_, info)| info.defines_type(path));
+            match (definitions.next(), definitions.next()) {
+                (Some((other, _)), None) => Some((other.clone(), path.to_string())),
+                _ => None,
+            }
+        })
+    }
+
+    /// Module and name of the trait a trait path used in a module points to, e.g. `Trait<T>`
+    pub fn resolve_trait(&self, module: &str, path: &str) -> Option<(String, String)> {
+        let path = path.split('<').next().unwrap_or(path).trim();
+        self.resolve_type(module, path).filter(|(target, name)| {
+            self.module_map
+                .get(target)
+                .is_some_and(|info| info.traits.iter().any(|info| info.name == *name))
+        })
+    }
+
+    fn resolve_type_in(&self, module: &str, path: &str, depth: usize) -> Option<(String, String)> {
+        // Guard against `use` cycles
+        if depth > 8 {
+            return None;
+        }
+        let info = self.module_map.get(module)?;
+        let (target, name) = match path.rsplit_once("::") {
+            Some((prefix, name)) => {
+                let prefix = expand_import(&info.uses, prefix);
+                let target = self.resolve_module_path(module, &prefix)?;
+                // Every segment of the prefix must be a module
+                let last = prefix.rsplit("::").next().unwrap_or_default();
+                let is_module = matches!(last, "crate" | "self" | "super")
+                    || self.module_path(&target).rsplit("::").next() == Some(last);
+                if !is_module {
+                    return None;
+                }
+                (target, name)
+            }
+            None => (module.to_string(), path),
+        };
+
+        let target_info = self.module_map.get(&target)?;
+        if target_info.defines_type(name) {
+            return Some((target, name.to_string()));
+        }
+
+        let imported = format!("::{}", name);
+        for used in &target_info.uses {
+            let resolved = if used.ends_with(&imported) {
+                self.resolve_type_in(&target, used, depth + 1)
+            } else if let Some(glob) = used.strip_suffix("::*") {
+                self.resolve_type_in(&target, &format!("{}::{}", glob, name), depth + 1)
+            } else {
+                None
+            };
+            if resolved.is_some() {
+                return resolved;
+            }
+        }
+        None
+    }
+
+    /// Rust path of a module, e.g. `my_crate::analyzer`
+    pub fn module_path(&self, module: &str) -> String {
+        let mut segments = Vec::new();
+        let mut current = module.to_string();
+        for _ in 0..self.module_map.len() {
+            let name = current
+                .strip_suffix("__lib")
+                .or_else(|| current.strip_suffix("__main"))
+                .unwrap_or_else(|| current.rsplit("__").next().unwrap_or_default());
+            segments.push(name.to_string());
+            match self.parent_module(&current) {
+                Some(parent) if parent != module => current = parent.clone(),
+                _ => break,
+            }
+        }
+        segments.reverse();
+        segments.join("::")
+    }
+
+    /// Record a call made in the current function
+    fn record_call(&mut self, callee: String, method: bool, on_self: bool) {
+        if self.function_context.is_empty() {
+            return;
+        }
+
+        let call = CallInfo {
+            caller: self.function_context.join("::"),
+            callee,
+            method,
+            on_self,
+        };
+        let calls = &mut self
+            .module_map
+            .entry(self.current_module.clone())
+            .or_default()
+            .calls;
+        if !calls.contains(&call) {
+            calls.push(call);
+        }
+    }
+}
+
+fn mark_functions(functions: &mut [FunctionInfo], source: Option<&[FunctionInfo]>) {
+    for function in functions {
+        function.macro_generated =
+            source.is_none_or(|source| !source.iter().any(|other| other.name == function.name));
+    }
 }
 
 impl<'ast> Visit<'ast> for ModulesVisitor {
     fn visit_item_struct(&mut self, item_struct: &'ast ItemStruct) {
         let struct_name = item_struct.ident.to_string();
         let description = extract_doc_comment(&item_struct.attrs);
-        let members = extract_struct_members(&item_struct.fields, &self.current_module);
+        let members = extract_struct_members(&item_struct.fields);
+        let derives = extract_derives(&item_struct.attrs);
+        let error_variants = if derives_error(&derives) {
+            vec![extract_error_variant(
+                &struct_name,
+                &item_struct.attrs,
+                &item_struct.fields,
+            )]
+        } else {
+            Vec::new()
+        };
 
         self.module_map
             .entry(self.current_module.clone())
-            .or_insert_with(|| ModuleInfo {
-                structs: BTreeMap::new(),
-                enums: BTreeMap::new(),
-                traits: Vec::new(),
-                submodules: Vec::new(),
-                functions: Vec::new(),
-                description: String::new(),
-            })
+            .or_default()
             .structs
             .insert(
                 struct_name.clone(),
                 StructInfo {
-                    derives: extract_derives(&item_struct.attrs),
+                    derives,
                     impl_traits: Vec::new(),
                     functions: Vec::new(),
                     members,
                     description,
+                    macro_generated: false,
+                    impl_only: false,
+                    error_variants,
                 },
             );
 
+        self.record_attributes(&item_struct.attrs, &struct_name, &struct_name);
         syn::visit::visit_item_struct(self, item_struct);
     }
 
     fn visit_item_impl(&mut self, item_impl: &'ast ItemImpl) {
+        let blanket = extract_blanket_impl(item_impl);
+        if let Some(blanket) = &blanket {
+            self.module_map
+                .entry(self.current_module.clone())
+                .or_default()
+                .blanket_impls
+                .push(blanket.clone());
+        }
+
         if let syn::Type::Path(TypePath { path, .. }) = &*item_impl.self_ty {
-            if let Some(struct_name) = path.get_ident() {
+            // Generic types like `Type<T>` are recorded by their name, std types are skipped
+            let struct_name = match (path.segments.first(), path.segments.len()) {
+                (Some(segment), 1) if blanket.is_none() => Some(&segment.ident).filter(|ident| {
+                    segment.arguments.is_none() || !STD_TYPES.contains(&ident.to_string().as_str())
+                }),
+                _ => None,
+            };
+            if let Some(struct_name) = struct_name {
                 let struct_name = struct_name.to_string();
                 let module_info = self
                     .module_map
                     .entry(self.current_module.clone())
-                    .or_insert_with(|| ModuleInfo {
-                        structs: BTreeMap::new(),
-                        enums: BTreeMap::new(),
-                        traits: Vec::new(),
-                        submodules: Vec::new(),
-                        functions: Vec::new(),
-                        description: String::new(),
-                    });
+                    .or_default();
+
+                // Expanded `#[derive(..)]`: Add the derive without its functions
+                if let Some((_, trait_path, _)) = &item_impl.trait_ {
+                    if is_automatically_derived(&item_impl.attrs) {
+                        let derive = trait_path
+                            .segments
+                            .last()
+                            .map(|segment| segment.ident.to_string())
+                            .unwrap_or_default();
+                        // Marker traits added by the compiler, e.g. for `PartialEq`
+                        if derive.starts_with("Structural") {
+                            return;
+                        }
+                        let derives = match module_info.enums.get_mut(&struct_name) {
+                            Some(enum_info) => &mut enum_info.derives,
+                            None => {
+                                &mut module_info
+                                    .structs
+                                    .entry(struct_name)
+                                    .or_insert_with(StructInfo::impl_only)
+                                    .derives
+                            }
+                        };
+                        if !derives.contains(&derive) {
+                            derives.push(derive);
+                        }
+                        return;
+                    }
+                }
 
                 // Retrieve or create the StructInfo
                 let struct_info = module_info
                     .structs
                     .entry(struct_name.clone())
-                    .or_insert_with(|| StructInfo {
-                        derives: Vec::new(),
-                        impl_traits: Vec::new(),
-                        functions: Vec::new(),
-                        members: Vec::new(),
-                        description: String::new(),
-                    });
+                    .or_insert_with(StructInfo::impl_only);
 
                 // Check if this impl block implements a trait
                 if let Some((_, trait_path, _)) = &item_impl.trait_ {
@@ -136,11 +685,10 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
                     for item in &item_impl.items {
                         if let syn::ImplItem::Fn(method) = item {
                             let fn_name = method.sig.ident.to_string();
-                            let parameters =
-                                extract_function_params(&method.sig.inputs, &self.current_module);
+                            let parameters = extract_function_params(&method.sig.inputs);
                             let return_type = match &method.sig.output {
                                 ReturnType::Default => "()".to_string(),
-                                ReturnType::Type(_, ty) => get_type_name(ty, &self.current_module),
+                                ReturnType::Type(_, ty) => get_type_name(ty),
                             };
 
                             let function_info = FunctionInfo {
@@ -149,6 +697,7 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
                                 public: true, // Trait impl functions are public
                                 return_type,
                                 description: extract_doc_comment(&method.attrs),
+                                macro_generated: false,
                             };
 
                             // Add function to the struct's function list
@@ -160,11 +709,10 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
                     for item in &item_impl.items {
                         if let syn::ImplItem::Fn(method) = item {
                             let fn_name = method.sig.ident.to_string();
-                            let parameters =
-                                extract_function_params(&method.sig.inputs, &self.current_module);
+                            let parameters = extract_function_params(&method.sig.inputs);
                             let return_type = match &method.sig.output {
                                 ReturnType::Default => "()".to_string(),
-                                ReturnType::Type(_, ty) => get_type_name(ty, &self.current_module),
+                                ReturnType::Type(_, ty) => get_type_name(ty),
                             };
 
                             // Check if the function is public or private
@@ -176,6 +724,7 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
                                 public,
                                 return_type,
                                 description: extract_doc_comment(&method.attrs),
+                                macro_generated: false,
                             };
 
                             // Add function to the struct's function list
@@ -186,45 +735,80 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
             }
         }
 
+        let owner = match &*item_impl.self_ty {
+            syn::Type::Path(TypePath { path, .. }) => path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        let owner = owner.unwrap_or_default();
+        let item = match &item_impl.trait_ {
+            Some((_, trait_path, _)) => format!("impl {} for {}", format_path(trait_path), owner),
+            None => format!("impl {}", owner),
+        };
+        self.record_attributes(&item_impl.attrs, &item, &owner);
+        self.function_context.push(owner);
         syn::visit::visit_item_impl(self, item_impl);
+        self.function_context.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'ast ImplItemFn) {
+        let owner = self.function_context.last().cloned().unwrap_or_default();
+        let item = format!("{}::{}", owner, impl_item_fn.sig.ident);
+        self.record_attributes(&impl_item_fn.attrs, &item, &owner);
+        self.function_context
+            .push(impl_item_fn.sig.ident.to_string());
+        syn::visit::visit_impl_item_fn(self, impl_item_fn);
+        self.function_context.pop();
     }
 
     fn visit_item_enum(&mut self, item_enum: &'ast ItemEnum) {
         let enum_name = item_enum.ident.to_string();
         let description = extract_doc_comment(&item_enum.attrs);
-        let variants = extract_enum_variants(&item_enum);
+        let variants = extract_enum_variants(item_enum);
+        let derives = extract_derives(&item_enum.attrs);
+        let error_variants = if derives_error(&derives) {
+            item_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let name = variant.ident.to_string();
+                    extract_error_variant(&name, &variant.attrs, &variant.fields)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
         self.module_map
             .entry(self.current_module.clone())
-            .or_insert_with(|| ModuleInfo {
-                structs: BTreeMap::new(),
-                enums: BTreeMap::new(),
-                traits: Vec::new(),
-                submodules: Vec::new(),
-                functions: Vec::new(),
-                description: String::new(),
-            })
+            .or_default()
             .enums
             .insert(
                 enum_name.clone(),
                 EnumInfo {
                     variants,
-                    derives: extract_derives(&item_enum.attrs),
+                    derives,
                     impl_traits: Vec::new(),
+                    functions: Vec::new(),
                     description,
+                    macro_generated: false,
+                    error_variants,
                 },
             );
 
+        self.record_attributes(&item_enum.attrs, &enum_name, &enum_name);
         syn::visit::visit_item_enum(self, item_enum);
     }
 
     fn visit_item_fn(&mut self, item_fn: &'ast ItemFn) {
         let fn_name = item_fn.sig.ident.to_string();
         let description = extract_doc_comment(&item_fn.attrs);
-        let parameters = extract_function_params(&item_fn.sig.inputs, &self.current_module);
+        let parameters = extract_function_params(&item_fn.sig.inputs);
         let return_type = match &item_fn.sig.output {
             ReturnType::Default => "()".to_string(),
-            ReturnType::Type(_, ty) => get_type_name(ty, &self.current_module),
+            ReturnType::Type(_, ty) => get_type_name(ty),
         };
 
         let public = matches!(item_fn.vis, syn::Visibility::Public(_));
@@ -235,46 +819,80 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
             public,
             return_type,
             description,
+            macro_generated: false,
         };
 
         self.module_map
             .entry(self.current_module.clone())
-            .or_insert_with(|| ModuleInfo {
-                structs: BTreeMap::new(),
-                enums: BTreeMap::new(),
-                traits: Vec::new(),
-                submodules: Vec::new(),
-                functions: Vec::new(),
-                description: String::new(),
-            })
+            .or_default()
             .functions
             .push(function_info.clone());
 
+        self.record_attributes(&item_fn.attrs, &fn_name, "");
+        self.function_context.push(fn_name);
         syn::visit::visit_item_fn(self, item_fn);
+        self.function_context.pop();
+    }
+
+    fn visit_file(&mut self, file: &'ast syn::File) {
+        // Crate and module docs (`//!`)
+        let description = extract_inner_doc_comment(&file.attrs);
+        if !description.is_empty() {
+            self.module_map
+                .entry(self.current_module.clone())
+                .or_default()
+                .description = description;
+        }
+
+        // `#![cfg(feature = "..")]` gates the whole module file
+        let module_item = format!("mod {}", self.current_module);
+        self.module_map
+            .entry(self.current_module.clone())
+            .or_default()
+            .feature_gates
+            .extend(
+                extract_cfg_features(&file.attrs)
+                    .into_iter()
+                    .map(|feature| FeatureGate {
+                        feature,
+                        item: module_item.clone(),
+                    }),
+            );
+
+        syn::visit::visit_file(self, file);
     }
 
     fn visit_item_mod(&mut self, item_mod: &'ast ItemMod) {
-        let description = extract_doc_comment(&item_mod.attrs);
+        // Test modules are not part of the crate's structure
+        if is_cfg_test(&item_mod.attrs) {
+            return;
+        }
+
+        let module_name = submodule_key(&self.current_module, &item_mod.ident.to_string());
+        let mut description = extract_doc_comment(&item_mod.attrs);
+        if description.is_empty() {
+            description = extract_inner_doc_comment(&item_mod.attrs);
+        }
 
         self.module_map
             .entry(self.current_module.clone())
-            .or_insert_with(|| ModuleInfo {
-                structs: BTreeMap::new(),
-                enums: BTreeMap::new(),
-                traits: Vec::new(),
-                submodules: Vec::new(),
-                functions: Vec::new(),
-                description: String::new(),
-            })
+            .or_default()
             .submodules
-            .push(item_mod.ident.to_string());
+            .push(module_name.clone());
 
         self.module_map
-            .get_mut(&self.current_module)
-            .unwrap()
+            .entry(module_name.clone())
+            .or_default()
             .description = description;
 
-        syn::visit::visit_item_mod(self, item_mod);
+        self.record_attributes(&item_mod.attrs, &format!("mod {}", module_name), "");
+
+        // Inline modules (e.g. in macro expanded sources) are visited as their own module
+        if item_mod.content.is_some() {
+            let parent_module = std::mem::replace(&mut self.current_module, module_name);
+            syn::visit::visit_item_mod(self, item_mod);
+            self.current_module = parent_module;
+        }
     }
 
     fn visit_item_trait(&mut self, item_trait: &'ast ItemTrait) {
@@ -287,10 +905,10 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
         for item in &item_trait.items {
             if let TraitItem::Fn(TraitItemFn { sig, attrs, .. }) = item {
                 let fn_name = sig.ident.to_string();
-                let parameters = extract_function_params(&sig.inputs, &self.current_module);
+                let parameters = extract_function_params(&sig.inputs);
                 let return_type = match &sig.output {
                     ReturnType::Default => "()".to_string(),
-                    ReturnType::Type(_, ty) => get_type_name(ty, &self.current_module),
+                    ReturnType::Type(_, ty) => get_type_name(ty),
                 };
 
                 let public = true; // Trait methods are generally public unless specified otherwise
@@ -303,6 +921,7 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
                     public,
                     return_type,
                     description,
+                    macro_generated: false,
                 });
             }
         }
@@ -311,37 +930,152 @@ impl<'ast> Visit<'ast> for ModulesVisitor {
         let trait_info = TraitInfo {
             name: trait_name,
             functions,
+            supertraits: item_trait
+                .supertraits
+                .iter()
+                .filter_map(|bound| match bound {
+                    TypeParamBound::Trait(trait_bound) => Some(format_path(&trait_bound.path)),
+                    _ => None,
+                })
+                .collect(),
             description,
+            macro_generated: false,
         };
 
         // Add the trait and its functions to the module map
         self.module_map
             .entry(self.current_module.clone())
-            .or_insert_with(|| ModuleInfo {
-                structs: BTreeMap::new(),
-                enums: BTreeMap::new(),
-                traits: Vec::new(),
-                submodules: Vec::new(),
-                functions: Vec::new(),
-                description: String::new(),
-            })
+            .or_default()
             .traits
             .push(trait_info.clone());
 
+        self.record_attributes(&item_trait.attrs, &trait_info.name, &trait_info.name);
+        self.function_context.push(trait_info.name);
         syn::visit::visit_item_trait(self, item_trait);
+        self.function_context.pop();
+    }
+
+    fn visit_trait_item_fn(&mut self, trait_item_fn: &'ast TraitItemFn) {
+        self.function_context
+            .push(trait_item_fn.sig.ident.to_string());
+        syn::visit::visit_trait_item_fn(self, trait_item_fn);
+        self.function_context.pop();
+    }
+
+    fn visit_expr_call(&mut self, expr_call: &'ast ExprCall) {
+        // Single segment paths are only calls in call position, otherwise they are variables
+        if let Expr::Path(ExprPath { path, .. }) = &*expr_call.func {
+            if path.segments.len() == 1 {
+                self.record_call(path_name(path), false, false);
+            }
+        }
+        syn::visit::visit_expr_call(self, expr_call);
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &'ast ExprMethodCall) {
+        let on_self = matches!(
+            &*expr_method_call.receiver,
+            Expr::Path(ExprPath { path, .. }) if path.is_ident("self")
+        );
+        self.record_call(expr_method_call.method.to_string(), true, on_self);
+        syn::visit::visit_expr_method_call(self, expr_method_call);
+    }
+
+    fn visit_expr_path(&mut self, expr_path: &'ast ExprPath) {
+        // Qualified paths like `Type::new` or `module::function`, also as function references
+        if expr_path.path.segments.len() > 1 {
+            self.record_call(path_name(&expr_path.path), false, false);
+        }
+        syn::visit::visit_expr_path(self, expr_path);
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        let is_local = path
+            .segments
+            .first()
+            .is_some_and(|segment| segment.ident == "Self");
+        if path.segments.len() > 1 && !is_local {
+            let segments: Vec<String> = path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            self.module_map
+                .entry(self.current_module.clone())
+                .or_default()
+                .path_references
+                .push(segments.join("::"));
+        }
+        syn::visit::visit_path(self, path);
+    }
+
+    fn visit_item_use(&mut self, item_use: &'ast ItemUse) {
+        let mut paths = Vec::new();
+        flatten_use_tree(String::new(), &item_use.tree, &mut paths);
+
+        self.module_map
+            .entry(self.current_module.clone())
+            .or_default()
+            .uses
+            .extend(paths);
+    }
+
+    fn visit_item_macro(&mut self, item_macro: &'ast ItemMacro) {
+        if let (true, Some(ident)) = (
+            item_macro.mac.path.is_ident("macro_rules"),
+            &item_macro.ident,
+        ) {
+            let macro_info = MacroInfo {
+                name: ident.to_string(),
+                exported: item_macro
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("macro_export")),
+                arms: count_macro_arms(item_macro.mac.tokens.clone()),
+                description: extract_doc_comment(&item_macro.attrs),
+            };
+
+            self.module_map
+                .entry(self.current_module.clone())
+                .or_default()
+                .macros
+                .push(macro_info);
+        }
+
+        syn::visit::visit_item_macro(self, item_macro);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if !mac.path.is_ident("macro_rules") {
+            let invocation = MacroInvocation {
+                name: path_name(&mac.path),
+                context: self.function_context.join("::"),
+            };
+
+            let invocations = &mut self
+                .module_map
+                .entry(self.current_module.clone())
+                .or_default()
+                .macro_invocations;
+            if !invocations.contains(&invocation) {
+                invocations.push(invocation);
+            }
+        }
+
+        syn::visit::visit_macro(self, mac);
     }
 }
 
-fn extract_struct_members(fields: &syn::Fields, current_module: &str) -> Vec<MemberInfo> {
+fn extract_struct_members(fields: &syn::Fields) -> Vec<MemberInfo> {
     fields
         .iter()
-        .filter_map(|field| {
+        .map(|field| {
             let name = field
                 .ident
                 .as_ref()
                 .map_or("<unnamed>".to_string(), |ident| ident.to_string());
-            let member_type = get_type_name(&field.ty, current_module); // Fully qualified name
-            Some(MemberInfo { name, member_type })
+            let member_type = get_type_name(&field.ty); // Fully qualified name
+            MemberInfo { name, member_type }
         })
         .collect()
 }
@@ -354,6 +1088,65 @@ fn extract_enum_variants(item_enum: &syn::ItemEnum) -> Vec<String> {
         .collect()
 }
 
+/// Derives `thiserror::Error` or another `Error` derive with the same attributes
+fn derives_error(derives: &[String]) -> bool {
+    derives
+        .iter()
+        .any(|derive| derive.rsplit("::").next() == Some("Error"))
+}
+
+/// Message of `#[error(..)]` and the fields wrapping other errors
+fn extract_error_variant(name: &str, attrs: &[Attribute], fields: &syn::Fields) -> ErrorVariant {
+    let message = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("error"))
+        .find_map(|attr| {
+            let arguments = attr
+                .parse_args_with(
+                    syn::punctuated::Punctuated::<Expr, syn::token::Comma>::parse_terminated,
+                )
+                .ok()?;
+            match arguments.first()? {
+                Expr::Lit(syn::ExprLit {
+                    lit: Lit::Str(message),
+                    ..
+                }) => Some(message.value()),
+                Expr::Path(path) => Some(path_name(&path.path)),
+                _ => None,
+            }
+        })
+        .unwrap_or_default();
+
+    let sources = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            let field_name = field
+                .ident
+                .as_ref()
+                .map_or(index.to_string(), |ident| ident.to_string());
+            let from = field.attrs.iter().any(|attr| attr.path().is_ident("from"));
+            let source = from
+                || field_name == "source"
+                || field
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("source"));
+            source.then(|| ErrorSource {
+                field: field_name,
+                source_type: get_type_name(&field.ty),
+                from,
+            })
+        })
+        .collect();
+
+    ErrorVariant {
+        name: name.to_string(),
+        message,
+        sources,
+    }
+}
+
 fn extract_derives(attrs: &[Attribute]) -> Vec<String> {
     attrs
         .iter()
@@ -372,42 +1165,317 @@ fn extract_derives(attrs: &[Attribute]) -> Vec<String> {
         .collect()
 }
 
-fn get_type_name(ty: &Type, current_module: &str) -> String {
+/// Replace an imported first segment with its `use` path, e.g. `fs::read` to `std::fs::read`
+pub(crate) fn expand_import(uses: &[String], path: &str) -> String {
+    let (first, rest) = path.split_once("::").unwrap_or((path, ""));
+    match uses
+        .iter()
+        .find(|used| used.rsplit("::").next() == Some(first))
+    {
+        Some(used) if !rest.is_empty() => format!("{}::{}", used, rest),
+        Some(used) => used.clone(),
+        None => path.to_string(),
+    }
+}
+
+/// Name of a submodule in the module map, qualified with the modules it is nested in
+///
+/// Submodules of the crate root are prefixed with the crate, deeper ones with their
+/// parent, e.g. `my_crate__store` for `store` and `my_crate__store__sqlite` for
+/// `store::sqlite`. Same-named modules of different parents and crates stay apart.
+pub fn submodule_key(parent: &str, name: &str) -> String {
+    let parent = parent
+        .strip_suffix("__lib")
+        .or_else(|| parent.strip_suffix("__main"))
+        .unwrap_or(parent);
+    match parent {
+        "" => name.to_string(),
+        parent => format!("{}__{}", parent, name),
+    }
+}
+
+/// Std and primitive types, only present in the model through their trait impls
+pub const STD_TYPES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64", "String", "Vec", "Option", "Result", "Box", "Rc", "Arc",
+    "PathBuf", "HashMap", "HashSet", "BTreeMap", "BTreeSet",
+];
+
+/// Attributes that are built into the compiler or belong to tools
+const BUILTIN_ATTRIBUTES: &[&str] = &[
+    "allow",
+    "automatically_derived",
+    "cfg",
+    "cfg_attr",
+    "cold",
+    "deny",
+    "deprecated",
+    "derive",
+    "doc",
+    "expect",
+    "export_name",
+    "forbid",
+    "global_allocator",
+    "ignore",
+    "inline",
+    "link",
+    "link_name",
+    "link_section",
+    "macro_export",
+    "macro_use",
+    "must_use",
+    "no_mangle",
+    "non_exhaustive",
+    "panic_handler",
+    "path",
+    "proc_macro",
+    "proc_macro_attribute",
+    "proc_macro_derive",
+    "repr",
+    "should_panic",
+    "target_feature",
+    "test",
+    "track_caller",
+    "used",
+    "warn",
+];
+
+/// Helper attributes of common derive macros, they are inert and not expanded on their own
+const DERIVE_HELPERS: &[(&str, &[&str])] = &[
+    ("Args", &["arg", "clap", "command", "group", "value"]),
+    (
+        "Builder",
+        &["builder", "builder_field_attr", "builder_struct_attr"],
+    ),
+    ("Default", &["default"]),
+    ("Deserialize", &["serde"]),
+    (
+        "Diagnostic",
+        &["diagnostic", "help", "label", "related", "source_code"],
+    ),
+    ("Display", &["display", "strum"]),
+    ("EnumIter", &["strum"]),
+    ("EnumString", &["strum"]),
+    ("Error", &["backtrace", "error", "from", "source"]),
+    ("FromRow", &["sqlx"]),
+    ("JsonSchema", &["schemars", "serde"]),
+    ("Parser", &["arg", "clap", "command", "group", "value"]),
+    ("Serialize", &["serde"]),
+    ("Subcommand", &["arg", "clap", "command", "group", "value"]),
+    ("ToSchema", &["schema", "serde"]),
+    ("Type", &["sqlx"]),
+    ("ValueEnum", &["clap", "value"]),
+];
+
+/// Blanket impl of a trait for a generic parameter of the impl
+fn extract_blanket_impl(item_impl: &ItemImpl) -> Option<BlanketImpl> {
+    let (_, trait_path, _) = item_impl.trait_.as_ref()?;
+    let syn::Type::Path(TypePath { path, .. }) = &*item_impl.self_ty else {
+        return None;
+    };
+    let ident = path.get_ident()?;
+    let param = item_impl
+        .generics
+        .type_params()
+        .find(|param| param.ident == *ident)?;
+
+    let where_bounds = item_impl
+        .generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| &where_clause.predicates)
+        .filter_map(|predicate| match predicate {
+            syn::WherePredicate::Type(predicate)
+                if matches!(&predicate.bounded_ty, Type::Path(bounded) if bounded.path.is_ident(ident)) =>
+            {
+                Some(&predicate.bounds)
+            }
+            _ => None,
+        })
+        .flatten();
+    let bounds = param
+        .bounds
+        .iter()
+        .chain(where_bounds)
+        .filter_map(|bound| match bound {
+            // `?Sized` relaxes a bound
+            TypeParamBound::Trait(bound)
+                if matches!(bound.modifier, syn::TraitBoundModifier::None) =>
+            {
+                Some(format_path(&bound.path))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Some(BlanketImpl {
+        trait_name: format_path(trait_path),
+        bounds,
+    })
+}
+
+/// Features required by `#[cfg(..)]` attributes, features inside `not(..)` are skipped
+fn extract_cfg_features(attrs: &[Attribute]) -> Vec<String> {
+    let mut features = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("cfg")) {
+        if let Ok(predicate) = attr.parse_args::<Meta>() {
+            collect_cfg_features(&predicate, &mut features);
+        }
+    }
+    features
+}
+
+/// Whether the item is only compiled for tests, i.e. `#[cfg(test)]`
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .any(|attr| {
+            attr.parse_args::<Meta>()
+                .is_ok_and(|predicate| predicate.path().is_ident("test"))
+        })
+}
+
+fn collect_cfg_features(predicate: &Meta, features: &mut Vec<String>) {
+    match predicate {
+        Meta::NameValue(name_value) if name_value.path.is_ident("feature") => {
+            if let Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(feature),
+                ..
+            }) = &name_value.value
+            {
+                if !features.contains(&feature.value()) {
+                    features.push(feature.value());
+                }
+            }
+        }
+        Meta::List(list) if list.path.is_ident("all") || list.path.is_ident("any") => {
+            let nested = list.parse_args_with(
+                syn::punctuated::Punctuated::<Meta, syn::token::Comma>::parse_terminated,
+            );
+            for predicate in nested.iter().flatten() {
+                collect_cfg_features(predicate, features);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Paths of attributes that are expanded by attribute macros, e.g. `tokio::main`
+///
+/// Helper attributes of the `derives` on the same item, e.g. `#[command]` of
+/// `clap::Parser`, are skipped.
+fn extract_macro_attributes(attrs: &[Attribute], derives: &[String]) -> Vec<String> {
+    let helpers: Vec<&str> = DERIVE_HELPERS
+        .iter()
+        .filter(|(derive, _)| {
+            derives
+                .iter()
+                .any(|path| path.rsplit("::").next() == Some(*derive))
+        })
+        .flat_map(|(_, helpers)| helpers.iter().copied())
+        .collect();
+
+    attrs
+        .iter()
+        .filter(|attr| {
+            let path = attr.path();
+            let is_tool = path.segments.len() > 1
+                && path.segments.first().is_some_and(|segment| {
+                    matches!(
+                        segment.ident.to_string().as_str(),
+                        "rustfmt" | "clippy" | "diagnostic"
+                    )
+                });
+            let is_builtin = path.get_ident().is_some_and(|ident| {
+                let ident = ident.to_string();
+                BUILTIN_ATTRIBUTES.contains(&ident.as_str()) || helpers.contains(&ident.as_str())
+            });
+            !is_tool && !is_builtin
+        })
+        .map(|attr| path_name(attr.path()))
+        .collect()
+}
+
+/// Collect the full paths of a use tree, groups are split into single paths
+fn flatten_use_tree(prefix: String, tree: &UseTree, paths: &mut Vec<String>) {
+    match tree {
+        UseTree::Path(path) => {
+            flatten_use_tree(format!("{}{}::", prefix, path.ident), &path.tree, paths)
+        }
+        // `use std::io::{self, Read}` imports `std::io`
+        UseTree::Name(name) if name.ident == "self" => {
+            paths.push(prefix.trim_end_matches("::").to_string())
+        }
+        UseTree::Name(name) => paths.push(format!("{}{}", prefix, name.ident)),
+        UseTree::Rename(rename) => paths.push(format!("{}{}", prefix, rename.ident)),
+        UseTree::Glob(_) => paths.push(format!("{}*", prefix)),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                flatten_use_tree(prefix.clone(), tree, paths);
+            }
+        }
+    }
+}
+
+fn get_type_name(ty: &Type) -> String {
     match ty {
         Type::Path(type_path) => type_path.to_token_stream().to_string(),
-        Type::Reference(type_ref) => format!("&{}", get_type_name(&*type_ref.elem, current_module)),
+        Type::Reference(type_ref) => format!("&{}", get_type_name(&type_ref.elem)),
         Type::Tuple(tuple_type) => {
-            let types: Vec<String> = tuple_type
-                .elems
-                .iter()
-                .map(|elem| get_type_name(elem, current_module))
-                .collect();
+            let types: Vec<String> = tuple_type.elems.iter().map(get_type_name).collect();
             format!("({})", types.join(", "))
         }
         Type::Slice(slice_type) => {
-            format!("[{}]", get_type_name(&*slice_type.elem, current_module))
+            format!("[{}]", get_type_name(&slice_type.elem))
         }
         _ => ty.into_token_stream().to_string(),
     }
 }
 
+/// Count the arms of a `macro_rules!` body, every arm has one top level `=>`
+pub(crate) fn count_macro_arms(tokens: TokenStream) -> usize {
+    let mut arms = 0;
+    let mut previous_eq = false;
+    for token in tokens {
+        let is_eq = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '=');
+        if previous_eq && matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '>') {
+            arms += 1;
+        }
+        previous_eq = is_eq;
+    }
+    arms
+}
+
+fn is_automatically_derived(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("automatically_derived"))
+}
+
 fn extract_doc_comment(attrs: &[Attribute]) -> String {
+    doc_comment(attrs, false)
+}
+
+/// Doc comment of the enclosing item, e.g. `//!` of a module
+fn extract_inner_doc_comment(attrs: &[Attribute]) -> String {
+    doc_comment(attrs, true)
+}
+
+fn doc_comment(attrs: &[Attribute], inner: bool) -> String {
     attrs
         .iter()
         .filter_map(|attr| {
-            match attr.style {
-                syn::AttrStyle::Outer => {
-                    if let Meta::NameValue(meta) = &attr.meta {
-                        if let syn::Expr::Lit(syn::ExprLit {
-                            lit: Lit::Str(lit_str),
-                            ..
-                        }) = &meta.value
-                        {
-                            return Some(lit_str.value());
-                        }
+            if matches!(attr.style, syn::AttrStyle::Inner(_)) == inner {
+                if let Meta::NameValue(meta) = &attr.meta {
+                    if let syn::Expr::Lit(syn::ExprLit {
+                        lit: Lit::Str(lit_str),
+                        ..
+                    }) = &meta.value
+                    {
+                        return Some(lit_str.value());
                     }
                 }
-                _ => {}
             }
             None
         })
@@ -417,7 +1485,6 @@ fn extract_doc_comment(attrs: &[Attribute]) -> String {
 
 fn extract_function_params(
     inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
-    current_module: &str,
 ) -> Vec<ParameterInfo> {
     inputs
         .iter()
@@ -426,7 +1493,7 @@ fn extract_function_params(
                 if let syn::Pat::Ident(PatIdent { ident, .. }) = &**pat {
                     return Some(ParameterInfo {
                         name: ident.to_string(),
-                        param_type: get_type_name(&**ty, current_module),
+                        param_type: get_type_name(ty),
                     });
                 }
             }
@@ -435,16 +1502,32 @@ fn extract_function_params(
         .collect()
 }
 
+/// Path with its generic arguments, e.g. `std::convert::From<Foo>`
 fn format_path(path: &syn::Path) -> String {
     path.segments
         .iter()
-        .map(|segment| {
-            if let PathArguments::None = segment.arguments {
-                segment.ident.to_string()
-            } else {
-                format!("{}<...>", segment.ident)
-            }
-        })
+        .map(|segment| compact_tokens(&segment.to_token_stream().to_string()))
         .collect::<Vec<_>>()
         .join("::")
 }
+
+/// Path without generic arguments, e.g. `Vec::new` for `Vec::<u8>::new`
+pub(crate) fn path_name(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Remove the spaces of printed tokens inside of paths, e.g. `From<&'a str>`
+pub fn compact_tokens(tokens: &str) -> String {
+    tokens
+        .replace(" :: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+        .replace(" (", "(")
+}
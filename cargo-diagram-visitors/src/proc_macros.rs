@@ -0,0 +1,93 @@
+//! Group derive and attribute macros by the crate that provides them
+use std::collections::BTreeMap;
+
+use crate::module_visitor::{ModuleInfo, ModulesVisitor};
+
+/// Derives that are built into the compiler
+const STD_DERIVES: &[&str] = &[
+    "Clone",
+    "Copy",
+    "Debug",
+    "Default",
+    "Eq",
+    "Hash",
+    "Ord",
+    "PartialEq",
+    "PartialOrd",
+];
+
+/// A derive or attribute macro and the items that use it
+#[derive(Debug, Clone, Default)]
+pub struct ProcMacro {
+    /// Name of the macro without its path, e.g. `Serialize`
+    pub name: String,
+    pub derive: bool,
+    pub users: Vec<ProcMacroUser>,
+}
+
+/// An item annotated with a proc macro
+#[derive(Debug, Clone, Default)]
+pub struct ProcMacroUser {
+    pub module: String,
+    pub item: String,
+    /// Type the item belongs to, empty for functions and modules
+    pub owner: String,
+}
+
+/// Collect the proc macros used by the analyzed crates, grouped by macro crate
+///
+/// Modules of dependency crates are skipped.
+pub fn group_proc_macros(visitor: &ModulesVisitor) -> BTreeMap<String, Vec<ProcMacro>> {
+    let mut groups: BTreeMap<String, BTreeMap<(bool, String), ProcMacro>> = BTreeMap::new();
+
+    for (module, info) in &visitor.module_map {
+        if visitor.dependency_modules.contains(module) {
+            continue;
+        }
+
+        for usage in &info.attribute_usages {
+            let name = usage.path.rsplit("::").next().unwrap_or_default();
+            let proc_macro = groups
+                .entry(macro_crate(info, &usage.path, usage.derive))
+                .or_default()
+                .entry((usage.derive, name.to_string()))
+                .or_insert_with(|| ProcMacro {
+                    name: name.to_string(),
+                    derive: usage.derive,
+                    users: Vec::new(),
+                });
+            proc_macro.users.push(ProcMacroUser {
+                module: module.clone(),
+                item: usage.item.clone(),
+                owner: usage.owner.clone(),
+            });
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(crate_name, macros)| (crate_name, macros.into_values().collect()))
+        .collect()
+}
+
+/// Crate that provides a macro, resolved through its path or the `use` statements of the module
+///
+/// Returns `crate` for macros re-exported by the analyzed crate and `unknown`
+/// if the macro can not be resolved, e.g. because of a glob import.
+pub fn macro_crate(info: &ModuleInfo, path: &str, derive: bool) -> String {
+    let full_path = match path.split_once("::") {
+        Some(_) => Some(path),
+        None if derive && STD_DERIVES.contains(&path) => return "std".to_string(),
+        None => info
+            .uses
+            .iter()
+            .find(|used| used.rsplit("::").next() == Some(path))
+            .map(String::as_str),
+    };
+
+    match full_path.and_then(|full_path| full_path.split("::").next()) {
+        Some("crate" | "self" | "super") => "crate".to_string(),
+        Some(crate_name) => crate_name.to_string(),
+        None => "unknown".to_string(),
+    }
+}
@@ -0,0 +1,616 @@
+//! Associations between types from the types of struct fields
+//!
+//! A field type is unwrapped to the types it refers to. Wrappers decide how the
+//! target is held: plain types and `Box` are owned (composition), `Rc` and `Arc`
+//! are shared (aggregation), references and `Weak` only point to the target
+//! (association). `Option` makes the target optional, collections, slices and
+//! arrays hold many.
+//!
+//! Function signatures only create dependencies, a type or module uses the types of
+//! its parameters and return types.
+//!
+//! Traits used as `dyn Trait` or `impl Trait` are targets as well, marked with
+//! their dispatch. These are the places where implementations can be swapped.
+use std::collections::BTreeMap;
+
+use syn::{GenericArgument, PathArguments, ReturnType, Type, TypeParamBound};
+
+use crate::module_visitor::{FunctionInfo, ModulesVisitor};
+
+/// How the containing type holds the target, ordered from strong to weak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Ownership {
+    Composition,
+    Aggregation,
+    Association,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Multiplicity {
+    One,
+    Optional,
+    Many,
+}
+
+impl Multiplicity {
+    /// UML label, empty for exactly one
+    pub fn label(&self) -> &'static str {
+        match self {
+            Multiplicity::One => "",
+            Multiplicity::Optional => "0..1",
+            Multiplicity::Many => "*",
+        }
+    }
+}
+
+/// How a trait is used in place of a concrete type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Dispatch {
+    /// `dyn Trait`, the implementation is chosen at runtime
+    Dyn,
+    /// `impl Trait`, the implementation is chosen at compile time
+    Impl,
+}
+
+impl Dispatch {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dispatch::Dyn => "dyn",
+            Dispatch::Impl => "impl",
+        }
+    }
+}
+
+/// A field of a struct referring to a struct, enum or trait
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Relation {
+    pub from_module: String,
+    pub from_type: String,
+    pub to_module: String,
+    pub to_type: String,
+    pub field: String,
+    pub ownership: Ownership,
+    pub multiplicity: Multiplicity,
+    /// Set if the field holds a trait object
+    pub dispatch: Option<Dispatch>,
+}
+
+/// A type or module using a type in the signature of one of its functions
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SignatureDependency {
+    pub from_module: String,
+    /// Owner of the functions, `None` for the free functions of the module
+    pub from_type: Option<String>,
+    pub to_module: String,
+    pub to_type: String,
+    /// Set if the trait is used as `dyn Trait` or `impl Trait`
+    pub dispatch: Option<Dispatch>,
+}
+
+/// Where a trait is used as `dyn Trait` or `impl Trait`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SitePosition {
+    Field,
+    Parameter,
+    Return,
+}
+
+impl SitePosition {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SitePosition::Field => "field",
+            SitePosition::Parameter => "parameter",
+            SitePosition::Return => "return",
+        }
+    }
+}
+
+/// A field, parameter or return type using a trait as `dyn Trait` or `impl Trait`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DispatchSite {
+    /// Path of the module, e.g. `my_crate::server`
+    pub module: String,
+    /// The field or function, e.g. `Server::handler` or `Server::new`
+    pub item: String,
+    pub position: SitePosition,
+    /// Name of the field or parameter, empty for return types
+    pub name: String,
+    pub dispatch: Dispatch,
+}
+
+/// A type referred to by another type
+struct Target {
+    path: String,
+    ownership: Ownership,
+    multiplicity: Multiplicity,
+    dispatch: Option<Dispatch>,
+}
+
+/// Wrappers holding their content the same way as the wrapper itself is held
+const TRANSPARENT: &[&str] = &[
+    "Cell", "RefCell", "Mutex", "RwLock", "Cow", "Pin", "OnceCell", "OnceLock",
+];
+const COLLECTIONS: &[&str] = &[
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+    "BinaryHeap",
+];
+/// Types without a value of their parameter
+const MARKERS: &[&str] = &["PhantomData", "PhantomPinned"];
+/// Traits added to trait objects without being dispatched on, e.g. `dyn Store + Send`
+const AUTO_TRAITS: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+/// Relations of the fields of all structs, dependency modules only with `dependencies`
+///
+/// Targets in dependency crates are skipped unless `dependencies` is set.
+pub fn field_relations(visitor: &ModulesVisitor, dependencies: bool) -> Vec<Relation> {
+    let is_shown = |module: &str| dependencies || !visitor.dependency_modules.contains(module);
+    let mut relations = Vec::new();
+
+    for (module, info) in &visitor.module_map {
+        if !is_shown(module) {
+            continue;
+        }
+        for (struct_name, struct_info) in &info.structs {
+            for member in &struct_info.members {
+                for target in type_targets(&member.member_type, Ownership::Composition) {
+                    let Some((to_module, to_type)) = visitor.resolve_type(module, &target.path)
+                    else {
+                        continue;
+                    };
+                    if !is_shown(&to_module) {
+                        continue;
+                    }
+                    let relation = Relation {
+                        from_module: module.clone(),
+                        from_type: struct_name.clone(),
+                        to_module,
+                        to_type,
+                        field: member.name.clone(),
+                        ownership: target.ownership,
+                        multiplicity: target.multiplicity,
+                        dispatch: target.dispatch,
+                    };
+                    if !relations.contains(&relation) {
+                        relations.push(relation);
+                    }
+                }
+            }
+        }
+    }
+    relations
+}
+
+/// Dependencies from the function signatures, private functions only with `private`
+///
+/// Every pair is listed once and pairs already connected by a field relation are
+/// skipped. Dependency modules and targets in them are only included with `dependencies`.
+pub fn signature_dependencies(
+    visitor: &ModulesVisitor,
+    dependencies: bool,
+    private: bool,
+) -> Vec<SignatureDependency> {
+    let is_shown = |module: &str| dependencies || !visitor.dependency_modules.contains(module);
+    let fields = field_relations(visitor, dependencies);
+    let mut uses = Vec::new();
+
+    for (module, info) in &visitor.module_map {
+        if !is_shown(module) {
+            continue;
+        }
+        let owners = info
+            .type_functions()
+            .map(|(owner, functions)| (Some(owner), functions))
+            .chain([(None, info.functions.as_slice())]);
+        for (owner, functions) in owners {
+            for target in signature_targets(functions, private) {
+                let Some((to_module, to_type)) = visitor.resolve_type(module, &target.path) else {
+                    continue;
+                };
+                let is_self = owner == Some(to_type.as_str()) && to_module == *module;
+                let is_field = owner.is_some_and(|owner| {
+                    fields.iter().any(|relation| {
+                        relation.from_module == *module
+                            && relation.from_type == owner
+                            && relation.to_module == to_module
+                            && relation.to_type == to_type
+                    })
+                });
+                if is_self || is_field || !is_shown(&to_module) {
+                    continue;
+                }
+
+                let dependency = SignatureDependency {
+                    from_module: module.clone(),
+                    from_type: owner.map(str::to_string),
+                    to_module,
+                    to_type,
+                    dispatch: target.dispatch,
+                };
+                if !uses.contains(&dependency) {
+                    uses.push(dependency);
+                }
+            }
+        }
+    }
+    uses
+}
+
+/// Fields, parameters and return types using traits as `dyn Trait` or `impl Trait`
+///
+/// Sites are grouped by the path of the trait. Traits outside of the model, e.g.
+/// `Fn` or `std::error::Error`, are listed by the path as written.
+pub fn dispatch_sites(
+    visitor: &ModulesVisitor,
+    dependencies: bool,
+) -> BTreeMap<String, Vec<DispatchSite>> {
+    let mut sites: BTreeMap<String, Vec<DispatchSite>> = BTreeMap::new();
+
+    for (module, info) in &visitor.module_map {
+        if !dependencies && visitor.dependency_modules.contains(module) {
+            continue;
+        }
+        let module_path = visitor.module_path(module);
+        let mut add = |item: String, position, name: &str, type_name: &str| {
+            for target in type_targets(type_name, Ownership::Association) {
+                let Some(dispatch) = target.dispatch else {
+                    continue;
+                };
+                let trait_path = match visitor.resolve_trait(module, &target.path) {
+                    Some((trait_module, name)) => {
+                        format!("{}::{}", visitor.module_path(&trait_module), name)
+                    }
+                    None => target.path,
+                };
+                let site = DispatchSite {
+                    module: module_path.clone(),
+                    item: item.clone(),
+                    position,
+                    name: name.to_string(),
+                    dispatch,
+                };
+                let trait_sites = sites.entry(trait_path).or_default();
+                if !trait_sites.contains(&site) {
+                    trait_sites.push(site);
+                }
+            }
+        };
+
+        for (struct_name, struct_info) in &info.structs {
+            for member in &struct_info.members {
+                let item = format!("{}::{}", struct_name, member.name);
+                add(item, SitePosition::Field, &member.name, &member.member_type);
+            }
+        }
+        let owners = info
+            .type_functions()
+            .map(|(owner, functions)| (Some(owner), functions))
+            .chain([(None, info.functions.as_slice())]);
+        for (owner, functions) in owners {
+            for function in functions {
+                let item = match owner {
+                    Some(owner) => format!("{}::{}", owner, function.name),
+                    None => function.name.clone(),
+                };
+                for parameter in &function.parameters {
+                    let position = SitePosition::Parameter;
+                    add(
+                        item.clone(),
+                        position,
+                        &parameter.name,
+                        &parameter.param_type,
+                    );
+                }
+                add(item, SitePosition::Return, "", &function.return_type);
+            }
+        }
+    }
+
+    for trait_sites in sites.values_mut() {
+        trait_sites.sort();
+    }
+    sites
+}
+
+/// Targets of the parameters and return types of functions
+fn signature_targets(functions: &[FunctionInfo], private: bool) -> Vec<Target> {
+    functions
+        .iter()
+        .filter(|function| private || function.public)
+        .flat_map(|function| {
+            function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.param_type.as_str())
+                .chain([function.return_type.as_str()])
+        })
+        .flat_map(|type_name| type_targets(type_name, Ownership::Association))
+        .collect()
+}
+
+/// Targets of a type as written in the model, nothing if it does not parse
+fn type_targets(type_name: &str, ownership: Ownership) -> Vec<Target> {
+    let mut targets = Vec::new();
+    if let Ok(ty) = syn::parse_str::<Type>(type_name) {
+        collect_targets(&ty, ownership, Multiplicity::One, &mut targets);
+    }
+    targets
+}
+
+/// Types referred to by a type with how they are held
+fn collect_targets(
+    ty: &Type,
+    ownership: Ownership,
+    multiplicity: Multiplicity,
+    targets: &mut Vec<Target>,
+) {
+    match ty {
+        Type::Reference(reference) => collect_targets(
+            &reference.elem,
+            Ownership::Association,
+            multiplicity,
+            targets,
+        ),
+        Type::Ptr(pointer) => {
+            collect_targets(&pointer.elem, Ownership::Association, multiplicity, targets)
+        }
+        Type::Slice(slice) => collect_targets(&slice.elem, ownership, Multiplicity::Many, targets),
+        Type::Array(array) => collect_targets(&array.elem, ownership, Multiplicity::Many, targets),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_targets(elem, ownership, multiplicity, targets);
+            }
+        }
+        Type::Paren(paren) => collect_targets(&paren.elem, ownership, multiplicity, targets),
+        Type::Group(group) => collect_targets(&group.elem, ownership, multiplicity, targets),
+        Type::TraitObject(object) => {
+            let bounds = object.bounds.iter();
+            collect_bounds(bounds, Dispatch::Dyn, ownership, multiplicity, targets)
+        }
+        Type::ImplTrait(object) => {
+            let bounds = object.bounds.iter();
+            collect_bounds(bounds, Dispatch::Impl, ownership, multiplicity, targets)
+        }
+        Type::BareFn(_) | Type::Never(_) | Type::Infer(_) => {}
+        Type::Path(type_path) => {
+            let Some(last) = type_path.path.segments.last() else {
+                return;
+            };
+            let wrapper = last.ident.to_string();
+            let arguments = || argument_types(&last.arguments);
+
+            let (ownership, multiplicity) = match wrapper.as_str() {
+                "Box" => (ownership, multiplicity),
+                "Rc" | "Arc" => (ownership.max(Ownership::Aggregation), multiplicity),
+                "Weak" => (Ownership::Association, multiplicity),
+                "Option" => (ownership, multiplicity.max(Multiplicity::Optional)),
+                name if COLLECTIONS.contains(&name) => (ownership, Multiplicity::Many),
+                name if TRANSPARENT.contains(&name) => (ownership, multiplicity),
+                name if MARKERS.contains(&name) => return,
+                _ => {
+                    // The type itself, its parameters are only referred to
+                    targets.push(Target {
+                        path: path_text(&type_path.path),
+                        ownership,
+                        multiplicity,
+                        dispatch: None,
+                    });
+                    for argument in arguments() {
+                        collect_targets(argument, Ownership::Association, multiplicity, targets);
+                    }
+                    return;
+                }
+            };
+            for argument in arguments() {
+                collect_targets(argument, ownership, multiplicity, targets);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Traits of `dyn` or `impl` bounds, their arguments are only referred to
+fn collect_bounds<'a>(
+    bounds: impl Iterator<Item = &'a TypeParamBound>,
+    dispatch: Dispatch,
+    ownership: Ownership,
+    multiplicity: Multiplicity,
+    targets: &mut Vec<Target>,
+) {
+    for bound in bounds {
+        let TypeParamBound::Trait(bound) = bound else {
+            continue;
+        };
+        let is_auto = bound
+            .path
+            .segments
+            .last()
+            .is_some_and(|last| AUTO_TRAITS.contains(&last.ident.to_string().as_str()));
+        if is_auto {
+            continue;
+        }
+        targets.push(Target {
+            path: path_text(&bound.path),
+            ownership,
+            multiplicity,
+            dispatch: Some(dispatch),
+        });
+        if let Some(last) = bound.path.segments.last() {
+            for argument in argument_types(&last.arguments) {
+                collect_targets(argument, Ownership::Association, multiplicity, targets);
+            }
+        }
+    }
+}
+
+/// Types in generic arguments, e.g. `T` and `Foo` of `<T, Item = Foo>` or `(A) -> B`
+fn argument_types(arguments: &PathArguments) -> Vec<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                GenericArgument::AssocType(assoc) => Some(&assoc.ty),
+                _ => None,
+            })
+            .collect(),
+        PathArguments::Parenthesized(arguments) => {
+            let mut types: Vec<&Type> = arguments.inputs.iter().collect();
+            if let ReturnType::Type(_, output) = &arguments.output {
+                types.push(output);
+            }
+            types
+        }
+        PathArguments::None => Vec::new(),
+    }
+}
+
+/// Path without generic arguments, e.g. `crate::model::Node`
+fn path_text(path: &syn::Path) -> String {
+    let segments: Vec<String> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    segments.join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::visit::Visit;
+
+    use super::*;
+
+    /// Relations of the structs in `code`, read as the root module `t__lib`
+    fn relations(code: &str) -> Vec<(String, String, Ownership, Multiplicity)> {
+        let mut visitor = ModulesVisitor {
+            current_module: "t__lib".to_string(),
+            ..Default::default()
+        };
+        visitor.visit_file(&syn::parse_file(code).unwrap());
+        field_relations(&visitor, false)
+            .into_iter()
+            .map(|relation| {
+                (
+                    relation.field,
+                    relation.to_type,
+                    relation.ownership,
+                    relation.multiplicity,
+                )
+            })
+            .collect()
+    }
+
+    fn relation(
+        field: &str,
+        to_type: &str,
+        ownership: Ownership,
+        multiplicity: Multiplicity,
+    ) -> (String, String, Ownership, Multiplicity) {
+        (
+            field.to_string(),
+            to_type.to_string(),
+            ownership,
+            multiplicity,
+        )
+    }
+
+    #[test]
+    fn owned_fields_are_compositions() {
+        let code = "struct Node; struct Tree { root: Node, boxed: Box<Node> }";
+        assert_eq!(
+            relations(code),
+            vec![
+                relation("root", "Node", Ownership::Composition, Multiplicity::One),
+                relation("boxed", "Node", Ownership::Composition, Multiplicity::One),
+            ]
+        );
+    }
+
+    #[test]
+    fn options_are_optional() {
+        let code = "struct Node; struct Tree { root: Option<Node>, boxed: Option<Box<Node>> }";
+        assert_eq!(
+            relations(code),
+            vec![
+                relation(
+                    "root",
+                    "Node",
+                    Ownership::Composition,
+                    Multiplicity::Optional
+                ),
+                relation(
+                    "boxed",
+                    "Node",
+                    Ownership::Composition,
+                    Multiplicity::Optional
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn collections_and_slices_hold_many() {
+        let code = "
+            struct Node;
+            struct Tree<'a> {
+                list: Vec<Node>,
+                map: HashMap<String, Node>,
+                slice: &'a [Node],
+            }
+        ";
+        assert_eq!(
+            relations(code),
+            vec![
+                relation("list", "Node", Ownership::Composition, Multiplicity::Many),
+                relation("map", "Node", Ownership::Composition, Multiplicity::Many),
+                relation("slice", "Node", Ownership::Association, Multiplicity::Many),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_fields_are_aggregations() {
+        let code = "struct Node; struct Tree { rc: Rc<Node>, arc: Arc<Node> }";
+        assert_eq!(
+            relations(code),
+            vec![
+                relation("rc", "Node", Ownership::Aggregation, Multiplicity::One),
+                relation("arc", "Node", Ownership::Aggregation, Multiplicity::One),
+            ]
+        );
+    }
+
+    #[test]
+    fn references_are_associations() {
+        let code = "struct Node; struct Tree<'a> { parent: &'a Node, weak: Weak<Node> }";
+        assert_eq!(
+            relations(code),
+            vec![
+                relation("parent", "Node", Ownership::Association, Multiplicity::One),
+                relation("weak", "Node", Ownership::Association, Multiplicity::One),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_are_matched_as_a_whole() {
+        // `FooBar` contains `Foo`, only the type itself is a target
+        let code = "struct Foo; struct FooBar; struct Holder { bar: FooBar }";
+        assert_eq!(
+            relations(code),
+            vec![relation(
+                "bar",
+                "FooBar",
+                Ownership::Composition,
+                Multiplicity::One
+            )]
+        );
+    }
+}
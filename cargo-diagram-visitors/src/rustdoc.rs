@@ -0,0 +1,465 @@
+//! Build the module model from rustdoc JSON output
+//!
+//! Generate the input with `cargo +nightly rustdoc -- -Z unstable-options
+//! --output-format json --document-private-items`. Unlike the syn based visitor
+//! this sees items generated by macros and resolved paths.
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::trace;
+
+use crate::json::{self, Json};
+use crate::module_visitor::{
+    count_macro_arms, submodule_key, EnumInfo, FunctionInfo, MacroInfo, MemberInfo, ModuleInfo,
+    ModulesVisitor, ParameterInfo, StructInfo, TraitInfo,
+};
+
+/// Analyze a rustdoc JSON file and add content to visitor
+pub fn analyze_rustdoc_json(file_path: &Path, visitor: &mut ModulesVisitor) {
+    let content = fs::read_to_string(file_path).expect("Konnte die Datei nicht lesen");
+    let document = json::parse(&content).expect("Fehler beim Parsen des JSON");
+
+    let Some(index) = document.get("index").and_then(Json::as_object) else {
+        return;
+    };
+    let Some(root) = document
+        .get("root")
+        .and_then(Json::to_text)
+        .and_then(|root| index.get(&root))
+    else {
+        return;
+    };
+
+    let crate_name = root.get("name").and_then(Json::as_str).unwrap_or("l");
+    let mut crate_reader = CrateReader {
+        index,
+        modules: BTreeMap::new(),
+    };
+    crate_reader.read_module(root, format!("{}__lib", crate_name));
+
+    visitor.merge(crate_reader.modules);
+}
+
+struct CrateReader<'a> {
+    index: &'a BTreeMap<String, Json>,
+    modules: BTreeMap<String, ModuleInfo>,
+}
+
+impl<'a> CrateReader<'a> {
+    fn item(&self, id: &Json) -> Option<&'a Json> {
+        id.to_text().and_then(|id| self.index.get(&id))
+    }
+
+    fn read_module(&mut self, item: &'a Json, module_name: String) {
+        trace!("Analysiere Modul: {}", module_name);
+        let mut info = ModuleInfo {
+            description: docs(item),
+            ..Default::default()
+        };
+
+        let items = inner(item, "module")
+            .and_then(|module| module.get("items"))
+            .map(Json::as_array)
+            .unwrap_or_default();
+
+        let mut submodules = Vec::new();
+        for child in items.iter().filter_map(|id| self.item(id)) {
+            let name = child
+                .get("name")
+                .and_then(Json::as_str)
+                .unwrap_or_default()
+                .to_string();
+
+            if inner(child, "module").is_some() {
+                let name = submodule_key(&module_name, &name);
+                info.submodules.push(name.clone());
+                submodules.push((child, name));
+            } else if let Some(inner) = inner(child, "struct") {
+                let struct_info = self.read_struct(child, inner);
+                info.structs.insert(name, struct_info);
+            } else if let Some(inner) = inner(child, "enum") {
+                let enum_info = self.read_enum(child, inner);
+                info.enums.insert(name, enum_info);
+            } else if let Some(inner) = inner(child, "trait") {
+                let functions = self.read_functions(inner.get("items"), true);
+                let supertraits = inner
+                    .get("bounds")
+                    .map(Json::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|bound| bound.get("trait_bound")?.get("trait"))
+                    .map(format_path)
+                    .collect();
+                info.traits.push(TraitInfo {
+                    name,
+                    functions,
+                    supertraits,
+                    description: docs(child),
+                    macro_generated: false,
+                });
+            } else if let Some(source) = inner(child, "macro").and_then(Json::as_str) {
+                info.macros.push(MacroInfo {
+                    name,
+                    exported: is_public(child),
+                    arms: macro_arms(source),
+                    description: docs(child),
+                });
+            } else if let Some(function) = read_function(child) {
+                info.functions.push(function);
+            }
+        }
+
+        self.modules.insert(module_name, info);
+
+        for (child, name) in submodules {
+            self.read_module(child, name);
+        }
+    }
+
+    fn read_struct(&self, item: &Json, inner: &Json) -> StructInfo {
+        let kind = inner.get("kind");
+        let mut members = Vec::new();
+
+        if let Some(fields) = kind.and_then(|kind| kind.get("plain")) {
+            // Older formats list the fields directly in the struct
+            let fields = fields.get("fields").or(inner.get("fields"));
+            for field in fields.map(Json::as_array).unwrap_or_default() {
+                if let Some(field) = self.item(field) {
+                    members.push(read_member(field));
+                }
+            }
+        } else if let Some(fields) = kind.and_then(|kind| kind.get("tuple")) {
+            // Stripped fields are null
+            for field in fields.as_array() {
+                if let Some(field) = self.item(field) {
+                    members.push(read_member(field));
+                }
+            }
+        }
+
+        let impls = self.read_impls(inner);
+        StructInfo {
+            derives: impls.derives,
+            impl_traits: impls.impl_traits,
+            functions: impls.functions,
+            members,
+            description: docs(item),
+            macro_generated: false,
+            impl_only: false,
+            error_variants: Vec::new(),
+        }
+    }
+
+    fn read_enum(&self, item: &Json, inner: &Json) -> EnumInfo {
+        let variants = inner
+            .get("variants")
+            .map(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|id| self.item(id))
+            .filter_map(|variant| variant.get("name").and_then(Json::as_str))
+            .map(str::to_string)
+            .collect();
+
+        let impls = self.read_impls(inner);
+        EnumInfo {
+            variants,
+            derives: impls.derives,
+            impl_traits: impls.impl_traits,
+            functions: impls.functions,
+            description: docs(item),
+            macro_generated: false,
+            error_variants: Vec::new(),
+        }
+    }
+
+    /// Collect derives, implemented traits and methods of a type
+    fn read_impls(&self, inner: &Json) -> Impls {
+        let mut impls = Impls::default();
+
+        let impl_items = inner.get("impls").map(Json::as_array).unwrap_or_default();
+        for item in impl_items.iter().filter_map(|id| self.item(id)) {
+            let Some(impl_inner) = self::inner(item, "impl") else {
+                continue;
+            };
+            // Blanket impls like `impl<T> From<T> for T` apply to every type
+            if impl_inner
+                .get("blanket_impl")
+                .is_some_and(|blanket| !blanket.is_null())
+            {
+                continue;
+            }
+
+            match impl_inner.get("trait").filter(|trait_| !trait_.is_null()) {
+                Some(trait_path) => {
+                    let trait_name = format_path(trait_path);
+                    if is_derived(item) {
+                        impls.derives.push(trait_name);
+                    } else {
+                        impls.impl_traits.push(trait_name);
+                        impls
+                            .functions
+                            .extend(self.read_functions(impl_inner.get("items"), true));
+                    }
+                }
+                None => impls
+                    .functions
+                    .extend(self.read_functions(impl_inner.get("items"), false)),
+            }
+        }
+
+        impls
+    }
+
+    fn read_functions(&self, items: Option<&Json>, public: bool) -> Vec<FunctionInfo> {
+        items
+            .map(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|id| self.item(id))
+            .filter_map(read_function)
+            .map(|mut function| {
+                function.public |= public;
+                function
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Impls {
+    derives: Vec<String>,
+    impl_traits: Vec<String>,
+    functions: Vec<FunctionInfo>,
+}
+
+/// Get the inner item of a kind, e.g. `{"inner": {"struct": {..}}}`
+fn inner<'a>(item: &'a Json, kind: &str) -> Option<&'a Json> {
+    item.get("inner").and_then(|inner| inner.get(kind))
+}
+
+fn docs(item: &Json) -> String {
+    item.get("docs")
+        .and_then(Json::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn is_public(item: &Json) -> bool {
+    item.get("visibility").and_then(Json::as_str) == Some("public")
+}
+
+fn is_derived(item: &Json) -> bool {
+    item.get("attrs")
+        .map(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .any(|attr| {
+            // Plain strings in older formats, tagged objects in newer ones
+            let text = attr
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| attr.get("other").and_then(Json::to_text))
+                .or_else(|| {
+                    attr.as_object()
+                        .and_then(|attr| attr.keys().next().cloned())
+                });
+            text.is_some_and(|text| text.contains("automatically_derived"))
+        })
+}
+
+fn read_member(field: &Json) -> MemberInfo {
+    MemberInfo {
+        name: field
+            .get("name")
+            .and_then(Json::as_str)
+            .unwrap_or("<unnamed>")
+            .to_string(),
+        member_type: inner(field, "struct_field")
+            .map(format_type)
+            .unwrap_or_default(),
+    }
+}
+
+fn read_function(item: &Json) -> Option<FunctionInfo> {
+    let function = inner(item, "function")?;
+    // `decl` in older formats
+    let sig = function.get("sig").or(function.get("decl"))?;
+
+    let parameters = sig
+        .get("inputs")
+        .map(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|input| {
+            let [name, ty] = input.as_array() else {
+                return None;
+            };
+            let name = name.as_str()?;
+            (name != "self").then(|| ParameterInfo {
+                name: name.to_string(),
+                param_type: format_type(ty),
+            })
+        })
+        .collect();
+
+    let return_type = match sig.get("output") {
+        Some(output) if !output.is_null() => format_type(output),
+        _ => "()".to_string(),
+    };
+
+    Some(FunctionInfo {
+        name: item.get("name").and_then(Json::as_str)?.to_string(),
+        parameters,
+        public: is_public(item),
+        return_type,
+        description: docs(item),
+        macro_generated: false,
+    })
+}
+
+/// Format a rustdoc path with its generic arguments
+fn format_path(path: &Json) -> String {
+    let name = path
+        .get("path")
+        .or(path.get("name"))
+        .and_then(Json::as_str)
+        .unwrap_or_default()
+        .trim_start_matches("$crate::");
+
+    let Some(args) = path
+        .get("args")
+        .and_then(|args| args.get("angle_bracketed"))
+    else {
+        return name.to_string();
+    };
+
+    let mut formatted: Vec<String> = args
+        .get("args")
+        .map(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|arg| {
+            arg.get("type")
+                .map(format_type)
+                .or_else(|| arg.get("lifetime").and_then(Json::to_text))
+                .or_else(|| {
+                    arg.get("const")
+                        .and_then(|value| value.get("expr"))
+                        .and_then(Json::to_text)
+                })
+        })
+        .collect();
+
+    // Associated type bindings like `Item = Foo`
+    let constraints = args.get("constraints").or(args.get("bindings"));
+    for constraint in constraints.map(Json::as_array).unwrap_or_default() {
+        let binding = constraint.get("binding");
+        if let (Some(name), Some(ty)) = (
+            constraint.get("name").and_then(Json::as_str),
+            binding
+                .and_then(|binding| binding.get("equality"))
+                .and_then(|equality| equality.get("type")),
+        ) {
+            formatted.push(format!("{} = {}", name, format_type(ty)));
+        }
+    }
+
+    if formatted.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}<{}>", name, formatted.join(", "))
+    }
+}
+
+/// Format a rustdoc type like the syn based visitor does
+fn format_type(ty: &Json) -> String {
+    let Some((kind, value)) = ty.as_object().and_then(|ty| ty.iter().next()) else {
+        return ty.to_text().unwrap_or_default();
+    };
+
+    match kind.as_str() {
+        "resolved_path" => format_path(value),
+        "primitive" | "generic" => value.to_text().unwrap_or_default(),
+        "borrowed_ref" => format!(
+            "&{}",
+            value.get("type").map(format_type).unwrap_or_default()
+        ),
+        "raw_pointer" => {
+            let mutable = value
+                .get("is_mutable")
+                .or(value.get("mutable"))
+                .and_then(Json::as_bool)
+                .unwrap_or(false);
+            format!(
+                "*{} {}",
+                if mutable { "mut" } else { "const" },
+                value.get("type").map(format_type).unwrap_or_default()
+            )
+        }
+        "tuple" => format!(
+            "({})",
+            value
+                .as_array()
+                .iter()
+                .map(format_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        "slice" => format!("[{}]", format_type(value)),
+        "array" => format!(
+            "[{}; {}]",
+            value.get("type").map(format_type).unwrap_or_default(),
+            value.get("len").and_then(Json::to_text).unwrap_or_default()
+        ),
+        "impl_trait" => format!("impl {}", format_bounds(value.as_array())),
+        "dyn_trait" => format!(
+            "dyn {}",
+            value
+                .get("traits")
+                .map(Json::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|bound| bound.get("trait"))
+                .map(format_path)
+                .collect::<Vec<_>>()
+                .join(" + ")
+        ),
+        "qualified_path" => format!(
+            "<{} as {}>::{}",
+            value.get("self_type").map(format_type).unwrap_or_default(),
+            value.get("trait").map(format_path).unwrap_or_default(),
+            value.get("name").and_then(Json::as_str).unwrap_or_default()
+        ),
+        "function_pointer" => "fn(..)".to_string(),
+        "infer" => "_".to_string(),
+        _ => kind.clone(),
+    }
+}
+
+fn format_bounds(bounds: &[Json]) -> String {
+    bounds
+        .iter()
+        .filter_map(|bound| {
+            bound
+                .get("trait_bound")
+                .and_then(|bound| bound.get("trait"))
+                .map(format_path)
+                .or_else(|| bound.get("outlives").and_then(Json::to_text))
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Arms of a `macro_rules!` from its source, e.g. `macro_rules! name { (..) => { .. }; }`
+fn macro_arms(source: &str) -> usize {
+    let Ok(tokens) = source.parse::<TokenStream>() else {
+        return 0;
+    };
+    // The arms are in the last group, `=>` inside of the arms are not counted
+    match tokens.into_iter().last() {
+        Some(TokenTree::Group(body)) => count_macro_arms(body.stream()),
+        _ => 0,
+    }
+}
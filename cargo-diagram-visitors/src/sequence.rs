@@ -0,0 +1,361 @@
+//! Sequence of the calls starting at a function, read again from the source files
+//!
+//! The calls of a function body are resolved against the edges of the [`CallGraph`],
+//! calls that are not part of the call graph are left out.
+use std::path::Path;
+
+use syn::visit::Visit;
+use syn::{
+    Block, Expr, ExprCall, ExprForLoop, ExprIf, ExprLoop, ExprMatch, ExprMethodCall, ExprWhile,
+    Item,
+};
+
+use crate::call_graph::{CallGraph, CallNode};
+use crate::flow::{function_body, text};
+use crate::module_visitor::ModulesVisitor;
+
+/// A type or module taking part in the sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    /// Unique identifier
+    pub id: String,
+    /// Type name, or the module path for free functions
+    pub name: String,
+}
+
+/// A message of the sequence
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Call {
+        from: String,
+        to: String,
+        function: String,
+        /// Messages sent by the called function
+        nested: Vec<Message>,
+    },
+    /// Branches of an `if` chain or `match`, with their condition or pattern
+    ///
+    /// The condition of a final `else` branch is empty.
+    Alt(Vec<(String, Vec<Message>)>),
+    /// `loop`, `while ..` or `for .. in ..`
+    Loop { header: String, body: Vec<Message> },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sequence {
+    /// Participants in the order of their first message
+    pub participants: Vec<Participant>,
+    pub messages: Vec<Message>,
+}
+
+impl Sequence {
+    /// Follow the calls from the start function up to `depth` calls deep
+    ///
+    /// Recursive calls are shown, but not followed again.
+    pub fn build(
+        visitor: &ModulesVisitor,
+        graph: &CallGraph,
+        start: &CallNode,
+        depth: Option<usize>,
+    ) -> Sequence {
+        let mut builder = SequenceBuilder {
+            visitor,
+            graph,
+            depth,
+            sequence: Sequence::default(),
+        };
+        builder.participant(start);
+        let messages = builder.function_messages(start, 0, &mut vec![start.clone()]);
+        builder.sequence.messages = messages;
+        builder.sequence
+    }
+}
+
+struct SequenceBuilder<'a> {
+    visitor: &'a ModulesVisitor,
+    graph: &'a CallGraph,
+    depth: Option<usize>,
+    sequence: Sequence,
+}
+
+impl SequenceBuilder<'_> {
+    /// Identifier of the participant owning a function, added on first use
+    fn participant(&mut self, node: &CallNode) -> String {
+        let module_path = self.visitor.module_path(&node.module);
+        let (id, name) = match node.function.rsplit_once("::") {
+            Some((owner, _)) => (format!("{}__{}", node.module, owner), owner.to_string()),
+            None => (node.module.clone(), module_path),
+        };
+
+        if !self.sequence.participants.iter().any(|p| p.id == id) {
+            self.sequence.participants.push(Participant {
+                id: id.clone(),
+                name,
+            });
+        }
+        id
+    }
+
+    fn function_messages(
+        &mut self,
+        node: &CallNode,
+        level: usize,
+        stack: &mut Vec<CallNode>,
+    ) -> Vec<Message> {
+        if self.depth.is_some_and(|depth| level >= depth) {
+            return Vec::new();
+        }
+        let Some(info) = self.visitor.module_map.get(&node.module) else {
+            return Vec::new();
+        };
+        let Some(body) = function_body(Path::new(&info.source_file), &node.function) else {
+            return Vec::new();
+        };
+
+        let steps = block_steps(&body);
+        self.messages(node, &steps, level, stack)
+    }
+
+    fn messages(
+        &mut self,
+        node: &CallNode,
+        steps: &[CallStep],
+        level: usize,
+        stack: &mut Vec<CallNode>,
+    ) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for step in steps {
+            match step {
+                CallStep::Call { path, method } => {
+                    let Some(callee) = self.resolve(node, path, *method) else {
+                        continue;
+                    };
+                    let from = self.participant(node);
+                    let to = self.participant(&callee);
+
+                    let mut nested = Vec::new();
+                    if !stack.contains(&callee) {
+                        stack.push(callee.clone());
+                        nested = self.function_messages(&callee, level + 1, stack);
+                        stack.pop();
+                    }
+
+                    let function = callee
+                        .function
+                        .rsplit("::")
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                    messages.push(Message::Call {
+                        from,
+                        to,
+                        function,
+                        nested,
+                    });
+                }
+                CallStep::Alt(branches) => {
+                    let branches: Vec<(String, Vec<Message>)> = branches
+                        .iter()
+                        .map(|(condition, steps)| {
+                            (condition.clone(), self.messages(node, steps, level, stack))
+                        })
+                        .collect();
+                    if branches.iter().any(|(_, messages)| !messages.is_empty()) {
+                        messages.push(Message::Alt(branches));
+                    }
+                }
+                CallStep::Loop { header, body } => {
+                    let body = self.messages(node, body, level, stack);
+                    if !body.is_empty() {
+                        messages.push(Message::Loop {
+                            header: header.clone(),
+                            body,
+                        });
+                    }
+                }
+            }
+        }
+        messages
+    }
+
+    /// The function called by the caller through the call graph
+    ///
+    /// Paths like `Type::new` have to match their type or module, this keeps
+    /// `String::new` apart from the `new` of a workspace type.
+    fn resolve(&self, caller: &CallNode, path: &str, method: bool) -> Option<CallNode> {
+        let segments: Vec<&str> = path.split("::").collect();
+        let (name, qualifier) = match segments.as_slice() {
+            [.., qualifier, name] if !method => (*name, Some(*qualifier)),
+            [.., name] => (*name, None),
+            [] => return None,
+        };
+        let owner = caller.function.rsplit_once("::").map(|(owner, _)| owner);
+        let qualifier = match qualifier {
+            Some("Self") => owner,
+            Some("crate" | "self" | "super") => None,
+            other => other,
+        };
+
+        let mut candidates = self
+            .graph
+            .edges
+            .iter()
+            .filter(|(from, _)| from == caller)
+            .map(|(_, to)| to)
+            .filter(|callee| {
+                let (callee_owner, callee_name) = match callee.function.rsplit_once("::") {
+                    Some((callee_owner, callee_name)) => (Some(callee_owner), callee_name),
+                    None => (None, callee.function.as_str()),
+                };
+                let module_name = self.visitor.module_path(&callee.module);
+                let module_name = module_name.rsplit("::").next();
+                callee_name == name
+                    && qualifier.is_none_or(|qualifier| {
+                        callee_owner == Some(qualifier)
+                            || (callee_owner.is_none() && module_name == Some(qualifier))
+                    })
+            });
+
+        let first = candidates.next()?;
+        candidates.next().is_none().then(|| first.clone())
+    }
+}
+
+/// Calls of a function body with the surrounding control flow
+#[derive(Debug, Clone, PartialEq)]
+enum CallStep {
+    /// Path of a function call without generic arguments or the name of a method
+    Call {
+        path: String,
+        method: bool,
+    },
+    Alt(Vec<(String, Vec<CallStep>)>),
+    Loop {
+        header: String,
+        body: Vec<CallStep>,
+    },
+}
+
+fn block_steps(block: &Block) -> Vec<CallStep> {
+    let mut collector = StepCollector::default();
+    collector.visit_block(block);
+    collector.0
+}
+
+/// Collects the calls in evaluation order, arguments before the call
+#[derive(Default)]
+struct StepCollector(Vec<CallStep>);
+
+impl StepCollector {
+    /// Steps collected by `visit` in a block of their own
+    fn nested(&mut self, visit: impl FnOnce(&mut Self)) -> Vec<CallStep> {
+        let outer = std::mem::take(&mut self.0);
+        visit(self);
+        std::mem::replace(&mut self.0, outer)
+    }
+}
+
+impl<'ast> Visit<'ast> for StepCollector {
+    fn visit_expr_call(&mut self, expr_call: &'ast ExprCall) {
+        syn::visit::visit_expr_call(self, expr_call);
+        if let Expr::Path(expr_path) = &*expr_call.func {
+            let path = expr_path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+            self.0.push(CallStep::Call {
+                path,
+                method: false,
+            });
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, expr_method_call: &'ast ExprMethodCall) {
+        syn::visit::visit_expr_method_call(self, expr_method_call);
+        self.0.push(CallStep::Call {
+            path: expr_method_call.method.to_string(),
+            method: true,
+        });
+    }
+
+    fn visit_expr_if(&mut self, expr_if: &'ast ExprIf) {
+        let mut branches = Vec::new();
+        let mut current = Some(expr_if);
+        while let Some(expr_if) = current.take() {
+            // The condition is evaluated before the branches
+            self.visit_expr(&expr_if.cond);
+            let then_steps = self.nested(|collector| collector.visit_block(&expr_if.then_branch));
+            branches.push((text(&expr_if.cond), then_steps));
+
+            match expr_if
+                .else_branch
+                .as_ref()
+                .map(|(_, else_expr)| &**else_expr)
+            {
+                Some(Expr::If(else_if)) => current = Some(else_if),
+                Some(else_expr) => {
+                    let else_steps = self.nested(|collector| collector.visit_expr(else_expr));
+                    branches.push((String::new(), else_steps));
+                }
+                None => {}
+            }
+        }
+        self.0.push(CallStep::Alt(branches));
+    }
+
+    fn visit_expr_match(&mut self, expr_match: &'ast ExprMatch) {
+        self.visit_expr(&expr_match.expr);
+        let branches = expr_match
+            .arms
+            .iter()
+            .map(|arm| {
+                let mut pattern = text(&arm.pat);
+                if let Some((_, guard)) = &arm.guard {
+                    pattern = format!("{} if {}", pattern, text(guard));
+                }
+                let steps = self.nested(|collector| {
+                    if let Some((_, guard)) = &arm.guard {
+                        collector.visit_expr(guard);
+                    }
+                    collector.visit_expr(&arm.body);
+                });
+                (pattern, steps)
+            })
+            .collect();
+        self.0.push(CallStep::Alt(branches));
+    }
+
+    fn visit_expr_loop(&mut self, expr_loop: &'ast ExprLoop) {
+        let body = self.nested(|collector| collector.visit_block(&expr_loop.body));
+        self.0.push(CallStep::Loop {
+            header: "loop".to_string(),
+            body,
+        });
+    }
+
+    fn visit_expr_while(&mut self, expr_while: &'ast ExprWhile) {
+        let body = self.nested(|collector| {
+            collector.visit_expr(&expr_while.cond);
+            collector.visit_block(&expr_while.body);
+        });
+        self.0.push(CallStep::Loop {
+            header: format!("while {}", text(&expr_while.cond)),
+            body,
+        });
+    }
+
+    fn visit_expr_for_loop(&mut self, expr_for: &'ast ExprForLoop) {
+        self.visit_expr(&expr_for.expr);
+        let body = self.nested(|collector| collector.visit_block(&expr_for.body));
+        self.0.push(CallStep::Loop {
+            header: format!("for {} in {}", text(&expr_for.pat), text(&expr_for.expr)),
+            body,
+        });
+    }
+
+    // Nested items are not part of the function
+    fn visit_item(&mut self, _: &'ast Item) {}
+}
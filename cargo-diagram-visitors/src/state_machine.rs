@@ -0,0 +1,478 @@
+//! State machine of an enum, read from the `match` expressions on its variants
+//!
+//! A `match` is a transition table if its arms match variants of the enum, e.g.
+//! `match self { State::Idle => .. }` or `match (self, event) { (State::Idle, Event::Start) => .. }`.
+//! Variants returned or assigned in an arm are the new states. Other patterns of the
+//! arm, or of `match` expressions nested in the arm, are the triggers of the transition.
+use std::collections::BTreeSet;
+use std::fs;
+
+use syn::visit::Visit;
+use syn::{Block, Expr, ExprMatch, ItemEnum, ItemImpl, ItemMod, Pat, Path, Stmt, Type};
+
+use crate::flow::text;
+use crate::module_visitor::{path_name, submodule_key, ModulesVisitor};
+
+/// A transition between two states
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    /// Patterns and guards of the arms leading to the transition, may be empty
+    pub trigger: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StateMachine {
+    /// Path of the enum, e.g. `my_crate::module::State`
+    pub name: String,
+    pub states: Vec<String>,
+    /// The `#[default]` variant
+    pub initial: Option<String>,
+    pub transitions: BTreeSet<Transition>,
+}
+
+impl StateMachine {
+    /// Module of an enum of the workspace, e.g. `State` or `module::State`
+    pub fn find_enum<'a>(visitor: &'a ModulesVisitor, enum_path: &str) -> Option<&'a String> {
+        let enum_path = enum_path.strip_prefix("crate::").unwrap_or(enum_path);
+        let enum_name = enum_path.rsplit("::").next()?;
+        let suffix = format!("::{}", enum_path);
+
+        visitor
+            .module_map
+            .iter()
+            .filter(|(module, _)| !visitor.dependency_modules.contains(*module))
+            .find(|(module, info)| {
+                let path = format!("{}::{}", visitor.module_path(module), enum_name);
+                info.enums.contains_key(enum_name) && (path == enum_path || path.ends_with(&suffix))
+            })
+            .map(|(module, _)| module)
+    }
+
+    /// Read the state machine of an enum, e.g. `State` or `module::State`
+    ///
+    /// The transitions are read from all analyzed source files of the workspace. Paths
+    /// are resolved in the module they are used in, so only the selected enum matches.
+    pub fn build(visitor: &ModulesVisitor, enum_path: &str) -> Option<StateMachine> {
+        let module = StateMachine::find_enum(visitor, enum_path)?;
+        let info = &visitor.module_map[module];
+        let enum_name = enum_path.rsplit("::").next()?;
+
+        let mut collector = TransitionCollector {
+            visitor,
+            enum_module: module,
+            enum_name,
+            states: &info.enums[enum_name].variants,
+            modules: Vec::new(),
+            self_types: Vec::new(),
+            initial: None,
+            transitions: BTreeSet::new(),
+        };
+
+        // Every file is visited once from its outermost module, inline modules are visited with it
+        let file_modules = visitor.module_map.iter().filter(|(module, info)| {
+            !visitor.dependency_modules.contains(*module)
+                && visitor
+                    .parent_module(module)
+                    .is_none_or(|parent| visitor.module_map[parent].source_file != info.source_file)
+        });
+        for (file_module, info) in file_modules {
+            let Ok(code) = fs::read_to_string(&info.source_file) else {
+                continue;
+            };
+            if !code.contains(enum_name) {
+                continue;
+            }
+            if let Ok(file) = syn::parse_file(&code) {
+                collector.modules = vec![file_module.clone()];
+                collector.visit_file(&file);
+            }
+        }
+
+        // A transition with a trigger covers the same transition without one
+        let transitions = collector
+            .transitions
+            .iter()
+            .filter(|transition| {
+                !transition.trigger.is_empty()
+                    || !collector.transitions.iter().any(|other| {
+                        other.from == transition.from
+                            && other.to == transition.to
+                            && !other.trigger.is_empty()
+                    })
+            })
+            .cloned()
+            .collect();
+
+        Some(StateMachine {
+            name: format!("{}::{}", visitor.module_path(module), enum_name),
+            states: collector.states.to_vec(),
+            initial: collector.initial,
+            transitions,
+        })
+    }
+}
+
+/// States matched by an arm pattern
+enum Source {
+    States(Vec<String>),
+    /// `_` or a binding, matches every state
+    Any,
+    /// The pattern does not match the enum
+    None,
+}
+
+struct TransitionCollector<'a> {
+    visitor: &'a ModulesVisitor,
+    /// Module defining the enum
+    enum_module: &'a str,
+    enum_name: &'a str,
+    states: &'a [String],
+    /// Module of the visited code, the innermost inline module last
+    modules: Vec<String>,
+    /// Whether the surrounding `impl` blocks are impls of the enum, `Self::Variant` is a state inside of them
+    self_types: Vec<bool>,
+    initial: Option<String>,
+    transitions: BTreeSet<Transition>,
+}
+
+impl TransitionCollector<'_> {
+    /// The state of a path like `State::Idle` or `Self::Idle`
+    fn state(&self, path: &Path) -> Option<String> {
+        let segments: Vec<String> = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        let [owner @ .., variant] = segments.as_slice() else {
+            return None;
+        };
+        let is_enum = match owner {
+            [] => false,
+            [owner] if owner == "Self" => self.self_types.last() == Some(&true),
+            owner => self.is_enum(&owner.join("::")),
+        };
+        (is_enum && self.states.contains(variant)).then(|| variant.clone())
+    }
+
+    /// The type path used in the current module points to the enum
+    fn is_enum(&self, path: &str) -> bool {
+        let Some(module) = self.modules.last() else {
+            return false;
+        };
+        self.visitor
+            .resolve_type(module, path)
+            .is_some_and(|(module, name)| module == self.enum_module && name == self.enum_name)
+    }
+
+    /// States matched by a pattern, the other parts of tuple patterns are added to `triggers`
+    fn source(&self, pat: &Pat, triggers: &mut Vec<String>) -> Source {
+        let path = match pat {
+            Pat::Path(pat_path) => Some(&pat_path.path),
+            Pat::TupleStruct(pat_tuple_struct) => Some(&pat_tuple_struct.path),
+            Pat::Struct(pat_struct) => Some(&pat_struct.path),
+            _ => None,
+        };
+        if let Some(path) = path {
+            return match self.state(path) {
+                Some(state) => Source::States(vec![state]),
+                None => Source::None,
+            };
+        }
+
+        match pat {
+            Pat::Wild(_) => Source::Any,
+            Pat::Ident(pat_ident) => match &pat_ident.subpat {
+                Some((_, subpat)) => self.source(subpat, triggers),
+                None => Source::Any,
+            },
+            Pat::Reference(pat_reference) => self.source(&pat_reference.pat, triggers),
+            Pat::Paren(pat_paren) => self.source(&pat_paren.pat, triggers),
+            Pat::Or(pat_or) => {
+                let mut states = Vec::new();
+                for case in &pat_or.cases {
+                    match self.source(case, &mut Vec::new()) {
+                        Source::States(case_states) => states.extend(case_states),
+                        Source::Any => return Source::Any,
+                        Source::None => return Source::None,
+                    }
+                }
+                Source::States(states)
+            }
+            Pat::Tuple(pat_tuple) => {
+                // The first element matching the enum is the state, the others trigger
+                let mut source = Source::None;
+                for elem in &pat_tuple.elems {
+                    let elem_source = self.source(elem, &mut Vec::new());
+                    match (&source, elem_source) {
+                        (Source::None, Source::States(states)) => source = Source::States(states),
+                        (Source::None, Source::Any) if !matches!(elem, Pat::Wild(_)) => {
+                            source = Source::Any
+                        }
+                        _ if !matches!(elem, Pat::Wild(_) | Pat::Ident(_)) => {
+                            triggers.push(text(elem))
+                        }
+                        _ => {}
+                    }
+                }
+                if matches!(source, Source::None) && !pat_tuple.elems.is_empty() {
+                    Source::Any
+                } else {
+                    source
+                }
+            }
+            _ => Source::None,
+        }
+    }
+
+    /// Add the transitions of a `match` on the states
+    ///
+    /// Returns false if the `match` does not match the enum.
+    fn match_transitions(&mut self, expr_match: &ExprMatch) -> bool {
+        let mut arms = Vec::new();
+        for arm in &expr_match.arms {
+            let mut triggers = Vec::new();
+            let source = self.source(&arm.pat, &mut triggers);
+            if let Some((_, guard)) = &arm.guard {
+                triggers.push(format!("[{}]", text(guard)));
+            }
+            arms.push((source, triggers, arm));
+        }
+        if !arms
+            .iter()
+            .any(|(source, _, _)| matches!(source, Source::States(_)))
+        {
+            return false;
+        }
+
+        // `_` covers the states of the arms before it without guard
+        let mut matched: BTreeSet<String> = BTreeSet::new();
+        for (source, triggers, arm) in arms {
+            let from = match source {
+                Source::States(states) => states,
+                Source::Any => self
+                    .states
+                    .iter()
+                    .filter(|state| !matched.contains(*state))
+                    .cloned()
+                    .collect(),
+                Source::None => continue,
+            };
+            if arm.guard.is_none() && triggers.is_empty() {
+                matched.extend(from.iter().cloned());
+            }
+
+            let mut targets = Vec::new();
+            self.targets(&arm.body, &triggers, &mut targets);
+            for (to, trigger) in targets {
+                for from in &from {
+                    self.transitions.insert(Transition {
+                        from: from.clone(),
+                        to: to.clone(),
+                        trigger: trigger.clone(),
+                    });
+                }
+            }
+        }
+        true
+    }
+
+    /// New states returned or assigned by an expression, with their triggers
+    fn targets(&self, expr: &Expr, triggers: &[String], targets: &mut Vec<(String, String)>) {
+        match expr {
+            Expr::Path(expr_path) => {
+                if let Some(state) = self.state(&expr_path.path) {
+                    targets.push((state, triggers.join(" ")));
+                }
+            }
+            Expr::Struct(expr_struct) => {
+                if let Some(state) = self.state(&expr_struct.path) {
+                    targets.push((state, triggers.join(" ")));
+                }
+            }
+            Expr::Call(expr_call) => {
+                if let Expr::Path(expr_path) = &*expr_call.func {
+                    match self.state(&expr_path.path) {
+                        Some(state) => targets.push((state, triggers.join(" "))),
+                        // Wrapped states, e.g. `Ok(State::Idle)` or `Some(State::Idle)`
+                        None => {
+                            for arg in &expr_call.args {
+                                self.targets(arg, triggers, targets);
+                            }
+                        }
+                    }
+                }
+            }
+            Expr::Paren(expr_paren) => self.targets(&expr_paren.expr, triggers, targets),
+            Expr::Block(expr_block) => self.block_targets(&expr_block.block, triggers, targets),
+            Expr::Unsafe(expr_unsafe) => self.block_targets(&expr_unsafe.block, triggers, targets),
+            Expr::If(expr_if) => {
+                let mut then_triggers = triggers.to_vec();
+                then_triggers.push(format!("[{}]", text(&expr_if.cond)));
+                self.block_targets(&expr_if.then_branch, &then_triggers, targets);
+                if let Some((_, else_expr)) = &expr_if.else_branch {
+                    self.targets(else_expr, triggers, targets);
+                }
+            }
+            Expr::Match(expr_match) => {
+                // A nested `match`, e.g. on the event
+                for arm in &expr_match.arms {
+                    let mut arm_triggers = triggers.to_vec();
+                    arm_triggers.push(text(&arm.pat));
+                    if let Some((_, guard)) = &arm.guard {
+                        arm_triggers.push(format!("[{}]", text(guard)));
+                    }
+                    self.targets(&arm.body, &arm_triggers, targets);
+                }
+            }
+            Expr::Return(expr_return) => {
+                if let Some(expr) = &expr_return.expr {
+                    self.targets(expr, triggers, targets);
+                }
+            }
+            Expr::Assign(expr_assign) => self.targets(&expr_assign.right, triggers, targets),
+            _ => {}
+        }
+    }
+
+    fn block_targets(
+        &self,
+        block: &Block,
+        triggers: &[String],
+        targets: &mut Vec<(String, String)>,
+    ) {
+        for stmt in &block.stmts {
+            if let Stmt::Expr(expr, _) = stmt {
+                self.targets(expr, triggers, targets);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for TransitionCollector<'_> {
+    fn visit_item_enum(&mut self, item_enum: &'ast ItemEnum) {
+        if item_enum.ident == self.enum_name
+            && self.modules.last().map(String::as_str) == Some(self.enum_module)
+        {
+            let default = item_enum.variants.iter().find(|variant| {
+                variant
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("default"))
+            });
+            if let Some(variant) = default {
+                self.initial = Some(variant.ident.to_string());
+            }
+        }
+        syn::visit::visit_item_enum(self, item_enum);
+    }
+
+    fn visit_item_impl(&mut self, item_impl: &'ast ItemImpl) {
+        let is_enum = match &*item_impl.self_ty {
+            Type::Path(type_path) => self.is_enum(&path_name(&type_path.path)),
+            _ => false,
+        };
+        self.self_types.push(is_enum);
+        syn::visit::visit_item_impl(self, item_impl);
+        self.self_types.pop();
+    }
+
+    fn visit_item_mod(&mut self, item_mod: &'ast ItemMod) {
+        let Some(module) = self.modules.last() else {
+            return;
+        };
+        let module = submodule_key(module, &item_mod.ident.to_string());
+        self.modules.push(module);
+        syn::visit::visit_item_mod(self, item_mod);
+        self.modules.pop();
+    }
+
+    fn visit_expr_match(&mut self, expr_match: &'ast ExprMatch) {
+        if !self.match_transitions(expr_match) {
+            syn::visit::visit_expr_match(self, expr_match);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze_file;
+
+    const DOOR: &str = "
+        #[derive(Default)]
+        pub enum State {
+            #[default]
+            Closed,
+            Open,
+        }
+
+        impl State {
+            pub fn next(self, event: Event) -> State {
+                match (self, event) {
+                    (State::Closed, Event::Push) => State::Open,
+                    (State::Open, Event::Pull) => Self::Closed,
+                    (state, _) => state,
+                }
+            }
+        }
+    ";
+
+    const LIGHT: &str = "
+        #[derive(Default)]
+        pub enum State {
+            Closed,
+            #[default]
+            Open,
+        }
+
+        pub fn toggle(state: State) -> State {
+            match state {
+                State::Open => State::Closed,
+                State::Closed => State::Open,
+            }
+        }
+    ";
+
+    fn transition(from: &str, to: &str, trigger: &str) -> Transition {
+        Transition {
+            from: from.to_string(),
+            to: to.to_string(),
+            trigger: trigger.to_string(),
+        }
+    }
+
+    #[test]
+    fn same_named_enums_stay_apart() {
+        let dir = std::env::temp_dir().join(format!("cargo-diagram-states-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.rs"), "pub mod door; pub mod light;").unwrap();
+        fs::write(dir.join("door.rs"), DOOR).unwrap();
+        fs::write(dir.join("light.rs"), LIGHT).unwrap();
+
+        let mut visitor = ModulesVisitor::default();
+        analyze_file(&dir.join("lib.rs"), &dir, "app__lib", &mut visitor, None);
+        let door = StateMachine::build(&visitor, "door::State").unwrap();
+        let light = StateMachine::build(&visitor, "light::State").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(door.name, "app::door::State");
+        assert_eq!(door.initial.as_deref(), Some("Closed"));
+        assert_eq!(
+            door.transitions.into_iter().collect::<Vec<_>>(),
+            [
+                transition("Closed", "Open", "Event::Push"),
+                transition("Open", "Closed", "Event::Pull"),
+            ]
+        );
+        assert_eq!(light.initial.as_deref(), Some("Open"));
+        assert_eq!(
+            light.transitions.into_iter().collect::<Vec<_>>(),
+            [
+                transition("Closed", "Open", ""),
+                transition("Open", "Closed", ""),
+            ]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Which types implement which traits
+//!
+//! Traits are compared by their last path segment without generic arguments, so
+//! `std::fmt::Debug` and `Debug` share a column. Blanket impls apply to every type
+//! implementing all of their bounds, which is only known for the traits in the model.
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::module_visitor::{BlanketImpl, ModulesVisitor, STD_TYPES};
+
+/// How a type implements a trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplKind {
+    Derive,
+    Impl,
+    Blanket,
+}
+
+impl ImplKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImplKind::Derive => "derive",
+            ImplKind::Impl => "impl",
+            ImplKind::Blanket => "blanket",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MatrixRow {
+    /// Path of the type, e.g. `my_crate::module::Type`
+    pub type_path: String,
+    pub is_enum: bool,
+    pub traits: BTreeMap<String, ImplKind>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TraitMatrix {
+    /// Columns, every trait implemented by at least one type
+    pub traits: BTreeSet<String>,
+    pub rows: Vec<MatrixRow>,
+}
+
+impl TraitMatrix {
+    /// Build the matrix of the analyzed types, dependency modules only with `dependencies`
+    pub fn build(visitor: &ModulesVisitor, dependencies: bool) -> TraitMatrix {
+        let mut matrix = TraitMatrix::default();
+        let mut blanket_impls: Vec<&BlanketImpl> = Vec::new();
+        let mut impl_rows = Vec::new();
+
+        for (module, info) in &visitor.module_map {
+            blanket_impls.extend(&info.blanket_impls);
+            if !dependencies && visitor.dependency_modules.contains(module) {
+                continue;
+            }
+
+            let module_path = visitor.module_path(module);
+            let structs = info
+                .structs
+                .iter()
+                .map(|(name, info)| (name, false, &info.derives, &info.impl_traits));
+            let enums = info
+                .enums
+                .iter()
+                .map(|(name, info)| (name, true, &info.derives, &info.impl_traits));
+            for (name, is_enum, derives, impl_traits) in structs.chain(enums) {
+                if STD_TYPES.contains(&name.as_str()) {
+                    continue;
+                }
+                let mut row = MatrixRow {
+                    type_path: format!("{}::{}", module_path, name),
+                    is_enum,
+                    traits: BTreeMap::new(),
+                };
+                for derive in derives {
+                    row.traits.insert(trait_name(derive), ImplKind::Derive);
+                }
+                for impl_trait in impl_traits {
+                    row.traits.insert(trait_name(impl_trait), ImplKind::Impl);
+                }
+                let impl_only = !is_enum && info.structs[name].impl_only;
+                if impl_only {
+                    impl_rows.push((name.clone(), row));
+                } else {
+                    matrix.rows.push(row);
+                }
+            }
+        }
+
+        // Impls of types defined in another module, e.g. `impl From<A> for other::B`
+        for (name, impl_row) in impl_rows {
+            let suffix = format!("::{}", name);
+            let mut definitions = matrix
+                .rows
+                .iter_mut()
+                .filter(|row| row.type_path.ends_with(&suffix));
+            match (definitions.next(), definitions.next()) {
+                (Some(definition), None) => {
+                    for (trait_name, kind) in impl_row.traits {
+                        definition.traits.entry(trait_name).or_insert(kind);
+                    }
+                }
+                _ => matrix.rows.push(impl_row),
+            }
+        }
+        matrix.rows.sort_by(|a, b| a.type_path.cmp(&b.type_path));
+
+        // Blanket impls may satisfy the bounds of other blanket impls
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for row in &mut matrix.rows {
+                for blanket in &blanket_impls {
+                    let name = trait_name(&blanket.trait_name);
+                    let applies = blanket
+                        .bounds
+                        .iter()
+                        .all(|bound| row.traits.contains_key(&trait_name(bound)));
+                    if applies && !row.traits.contains_key(&name) {
+                        row.traits.insert(name, ImplKind::Blanket);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        matrix.traits = matrix
+            .rows
+            .iter()
+            .flat_map(|row| row.traits.keys().cloned())
+            .collect();
+        matrix
+    }
+}
+
+/// Column of a trait path, e.g. `From` for `std::convert::From<T>`
+fn trait_name(path: &str) -> String {
+    let path = path.split('<').next().unwrap_or(path);
+    path.rsplit("::").next().unwrap_or(path).trim().to_string()
+}
@@ -1,41 +1,56 @@
 //! Analyze a repository with cargo-diagram-visitors
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
-use cargo_diagram_visitors::analyze_file;
+use cargo_diagram_visitors::crates::{CrateDependency, CrateInfo, WorkspaceInfo};
 use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::{analyze_expanded_file, analyze_file};
+
+use crate::manifest::{load_lock_file, Dependency, DependencyKind, LockedPackage, Manifest};
+
+/// Options for the analysis of dependency crates
+pub struct DependencyOptions {
+    /// Levels of dependencies to follow, 1 for direct dependencies only
+    pub depth: usize,
+    /// Only analyze these crates, all crates if empty
+    pub allow: Vec<String>,
+}
 
 /// Analyze a repository
-pub fn analyze_repository(current_dir: &Path, visitor: &mut ModulesVisitor) {
+///
+/// Extraction results are cached in `cache_dir` if given.
+pub fn analyze_repository(
+    current_dir: &Path,
+    visitor: &mut ModulesVisitor,
+    cache_dir: Option<&Path>,
+) {
     if current_dir.is_dir() {
         // Search for Cargo.toml
         if current_dir.join("Cargo.toml").exists() {
             debug!("Analysiere Crate: {:?}", current_dir);
-            analyze_crate_files(current_dir, visitor);
+            analyze_crate_files(current_dir, visitor, cache_dir);
         }
 
         // Analyze Subcrates
         for entry in fs::read_dir(current_dir).expect("Fehler beim Lesen des Verzeichnisses") {
             let entry = entry.expect("Fehler beim Lesen des Verzeichnis-Eintrags");
             let path = entry.path();
-            if path.file_name().unwrap() == "target" {
+            if is_skipped_dir(&path) {
                 continue;
             }
-            analyze_repository(&path, visitor); // Rekursive Analyse
+            analyze_repository(&path, visitor, cache_dir); // Rekursive Analyse
         }
     }
 }
 
 /// Analyze crate files (main.rs / lib.rs)
-fn analyze_crate_files(crate_dir: &Path, visitor: &mut ModulesVisitor) {
+fn analyze_crate_files(crate_dir: &Path, visitor: &mut ModulesVisitor, cache_dir: Option<&Path>) {
     let src_dir = crate_dir.join("src");
 
-    let file_name = crate_dir
-        .file_name()
-        .and_then(|name| name.to_str().map(|s| s.to_string()))
-        .unwrap_or("l".to_string())
-        .replace('-', "_");
+    let file_name = crate_prefix(crate_dir);
 
     // main.rs
     let main_file = src_dir.join("main.rs");
@@ -46,6 +61,7 @@ fn analyze_crate_files(crate_dir: &Path, visitor: &mut ModulesVisitor) {
             &src_dir,
             format!("{}__main", file_name).as_str(),
             visitor,
+            cache_dir,
         );
     }
 
@@ -58,6 +74,443 @@ fn analyze_crate_files(crate_dir: &Path, visitor: &mut ModulesVisitor) {
             &src_dir,
             format!("{}__lib", file_name).as_str(),
             visitor,
+            cache_dir,
+        );
+    }
+}
+
+/// Replace the analysis of a crate with its macro expanded sources
+pub fn analyze_expanded(
+    root: &Path,
+    crate_name: &str,
+    expanded_file: &Path,
+    visitor: &mut ModulesVisitor,
+) {
+    let crate_name = load_workspace_manifests(root)
+        .iter()
+        .find(|manifest| manifest.name.as_deref() == Some(crate_name))
+        .map(|manifest| crate_prefix(&manifest.dir))
+        .unwrap_or_else(|| crate_name.replace('-', "_"));
+    let lib_module = format!("{}__lib", crate_name);
+    let main_module = format!("{}__main", crate_name);
+
+    // Binary crates are only expanded as binary if there is no library
+    let module_name = if !visitor.module_map.contains_key(&lib_module)
+        && visitor.module_map.contains_key(&main_module)
+    {
+        main_module
+    } else {
+        lib_module
+    };
+
+    debug!("Analysiere expandierte Datei: {:?}", expanded_file);
+    analyze_expanded_file(expanded_file, &module_name, visitor);
+}
+
+/// Record the dependencies of the workspace crates in `ModulesVisitor::crate_dependencies`
+pub fn record_crate_dependencies(root: &Path, visitor: &mut ModulesVisitor) {
+    let manifests = load_workspace_manifests(root);
+
+    for manifest in &manifests {
+        let dependencies = manifest
+            .dependencies
+            .iter()
+            .filter(|dependency| dependency.kind == DependencyKind::Normal)
+            .map(|dependency| {
+                manifests
+                    .iter()
+                    .find(|other| other.name.as_ref() == Some(&dependency.package))
+                    .map(|manifest| crate_prefix(&manifest.dir))
+                    .unwrap_or_else(|| dependency.package.replace('-', "_"))
+            })
+            .collect();
+        visitor
+            .crate_dependencies
+            .insert(crate_prefix(&manifest.dir), dependencies);
+    }
+}
+
+/// Record the workspace crates with their dependencies in `ModulesVisitor::workspace`
+///
+/// Versions are taken from `Cargo.lock`, only local files are read.
+pub fn record_workspace_crates(root: &Path, visitor: &mut ModulesVisitor) {
+    let manifests = load_workspace_manifests(root);
+    let lock = load_lock_file(&root.join("Cargo.lock"));
+
+    let mut locked_versions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for locked in &lock {
+        locked_versions
+            .entry(locked.name.clone())
+            .or_default()
+            .insert(locked.version.clone());
+    }
+
+    let crates = manifests
+        .iter()
+        .filter_map(|manifest| {
+            let name = manifest.name.clone()?;
+            let locked = lock.iter().find(|locked| locked.name == name);
+            let dependencies = manifest
+                .dependencies
+                .iter()
+                .map(|dependency| CrateDependency {
+                    name: dependency.name.clone(),
+                    package: dependency.package.clone(),
+                    kind: dependency.kind,
+                    version: locked
+                        .and_then(|locked| dependent_locked_version(dependency, locked))
+                        .or_else(|| locked_version(dependency, &lock))
+                        .or_else(|| dependency.version.clone()),
+                    optional: dependency.optional,
+                    workspace: manifests
+                        .iter()
+                        .any(|other| other.name.as_ref() == Some(&dependency.package)),
+                })
+                .collect();
+
+            Some(CrateInfo {
+                name,
+                module_prefix: crate_prefix(&manifest.dir),
+                version: manifest.version.clone(),
+                dependencies,
+                features: manifest.features.clone(),
+            })
+        })
+        .collect();
+
+    visitor.workspace = WorkspaceInfo {
+        crates,
+        locked_versions,
+    };
+}
+
+/// Version of a dependency named in the lock entry of the dependent crate
+///
+/// The lock file only names the version if several versions are locked.
+fn dependent_locked_version(dependency: &Dependency, dependent: &LockedPackage) -> Option<String> {
+    let versions: Vec<&str> = dependent
+        .dependencies
+        .iter()
+        .filter_map(|entry| {
+            let mut parts = entry.split_whitespace();
+            (parts.next() == Some(dependency.package.as_str()))
+                .then(|| parts.next())
+                .flatten()
+        })
+        .collect();
+
+    // A dependent of several versions, e.g. as normal and dev dependency, needs the requirement
+    match versions.as_slice() {
+        [version] => Some(version.to_string()),
+        _ => None,
+    }
+}
+
+/// Root modules of workspace crates are named after the crate directory
+fn crate_prefix(crate_dir: &Path) -> String {
+    // `./` has no file name
+    let crate_dir = fs::canonicalize(crate_dir).unwrap_or_else(|_| crate_dir.to_path_buf());
+    crate_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("l")
+        .replace('-', "_")
+}
+
+/// Build artifacts and vendored sources are not part of the repository
+fn is_skipped_dir(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "target" || name == "vendor")
+}
+
+/// Find all crate directories of a repository
+pub fn find_crate_dirs(current_dir: &Path) -> Vec<PathBuf> {
+    let mut crate_dirs = Vec::new();
+    if current_dir.is_dir() {
+        if current_dir.join("Cargo.toml").exists() {
+            crate_dirs.push(current_dir.to_path_buf());
+        }
+
+        for entry in fs::read_dir(current_dir).expect("Fehler beim Lesen des Verzeichnisses") {
+            let path = entry
+                .expect("Fehler beim Lesen des Verzeichnis-Eintrags")
+                .path();
+            if !is_skipped_dir(&path) {
+                crate_dirs.extend(find_crate_dirs(&path));
+            }
+        }
+    }
+    crate_dirs
+}
+
+/// Read the manifests of all crates of a repository
+pub fn load_workspace_manifests(root: &Path) -> Vec<Manifest> {
+    let workspace = Manifest::load(root);
+
+    find_crate_dirs(root)
+        .iter()
+        .filter_map(|dir| Manifest::load(dir))
+        .map(|mut manifest| {
+            if let Some(workspace) = &workspace {
+                manifest.inherit_workspace(workspace);
+            }
+            manifest
+        })
+        .collect()
+}
+
+/// Analyze the dependency crates of a repository
+///
+/// Sources are only searched locally: path dependencies, `vendor/` and the
+/// cargo registry cache. Modules of dependencies are recorded in
+/// `ModulesVisitor::dependency_modules`.
+pub fn analyze_dependencies(
+    root: &Path,
+    visitor: &mut ModulesVisitor,
+    cache_dir: Option<&Path>,
+    options: &DependencyOptions,
+) {
+    let manifests = load_workspace_manifests(root);
+    let lock = load_lock_file(&root.join("Cargo.lock"));
+    let registry_dirs = registry_source_dirs();
+
+    let workspace_crates: BTreeSet<String> = manifests
+        .iter()
+        .filter_map(|manifest| manifest.name.clone())
+        .collect();
+
+    let mut queue: VecDeque<(Dependency, usize)> = manifests
+        .iter()
+        .flat_map(|manifest| manifest.dependencies.iter().cloned())
+        .filter(|dependency| dependency.kind == DependencyKind::Normal)
+        .map(|dependency| (dependency, 1))
+        .collect();
+    let mut visited = BTreeSet::new();
+
+    while let Some((dependency, depth)) = queue.pop_front() {
+        let package = &dependency.package;
+        if depth > options.depth
+            || workspace_crates.contains(package)
+            || (!options.allow.is_empty() && !options.allow.contains(package))
+            // Optional dependencies which are not enabled are missing in the lock file
+            || (!lock.is_empty() && !lock.iter().any(|locked| &locked.name == package))
+            || !visited.insert(package.clone())
+        {
+            continue;
+        }
+
+        let Some(manifest) = locate_dependency(&dependency, root, &lock, &registry_dirs)
+            .and_then(|dir| Manifest::load(&dir))
+        else {
+            debug!("Quellen von {} nicht gefunden", package);
+            continue;
+        };
+
+        debug!("Analysiere Abhängigkeit: {:?}", manifest.dir);
+        analyze_dependency_crate(&manifest, visitor, cache_dir);
+
+        queue.extend(
+            manifest
+                .dependencies
+                .into_iter()
+                .filter(|dependency| dependency.kind == DependencyKind::Normal)
+                .map(|dependency| (dependency, depth + 1)),
+        );
+    }
+}
+
+/// Analyze the library of a dependency crate
+fn analyze_dependency_crate(
+    manifest: &Manifest,
+    visitor: &mut ModulesVisitor,
+    cache_dir: Option<&Path>,
+) {
+    let Some(crate_name) = manifest.crate_name() else {
+        return;
+    };
+    let lib_file = manifest
+        .lib_path
+        .clone()
+        .unwrap_or_else(|| manifest.dir.join("src").join("lib.rs"));
+    if !lib_file.exists() {
+        return;
+    }
+
+    let root_module = format!("{}__lib", crate_name);
+    let mut crate_visitor = ModulesVisitor::default();
+    analyze_file(
+        &lib_file,
+        lib_file.parent().unwrap(),
+        &root_module,
+        &mut crate_visitor,
+        cache_dir,
+    );
+    let modules = crate_visitor.module_map;
+
+    visitor.dependency_modules.extend(modules.keys().cloned());
+    visitor.merge(modules);
+}
+
+/// Find the source directory of a dependency
+fn locate_dependency(
+    dependency: &Dependency,
+    root: &Path,
+    lock: &[LockedPackage],
+    registry_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    if let Some(path) = &dependency.path {
+        return path.join("Cargo.toml").exists().then(|| path.clone());
+    }
+
+    let package = &dependency.package;
+    let version = locked_version(dependency, lock);
+
+    let mut candidates = vec![root.join("vendor").join(package)];
+    if let Some(version) = &version {
+        let dir_name = format!("{}-{}", package, version);
+        candidates.push(root.join("vendor").join(&dir_name));
+        candidates.extend(registry_dirs.iter().map(|dir| dir.join(&dir_name)));
+    } else {
+        // Without a lock file take the newest version in the registry
+        let prefix = format!("{}-", package);
+        let newest = registry_dirs
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| {
+                let version = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix(&prefix))
+                    .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+                    .map(version_numbers)?;
+                Some((version, path))
+            })
+            .max()
+            .map(|(_, path)| path);
+        candidates.extend(newest);
+    }
+
+    candidates
+        .into_iter()
+        .find(|dir| dir.join("Cargo.toml").exists())
+}
+
+/// Version of a dependency according to the lock file
+fn locked_version(dependency: &Dependency, lock: &[LockedPackage]) -> Option<String> {
+    let versions: Vec<&String> = lock
+        .iter()
+        .filter(|locked| locked.name == dependency.package)
+        .map(|locked| &locked.version)
+        .collect();
+
+    // Several versions are locked: prefer the one matching the requirement
+    let requirement = dependency
+        .version
+        .as_deref()
+        .unwrap_or_default()
+        .trim_start_matches(['^', '~', '=', ' ']);
+    versions
+        .iter()
+        .find(|version| !requirement.is_empty() && matches_requirement(version, requirement))
+        .or(versions.last())
+        .map(|version| version.to_string())
+}
+
+/// Numbers of a version to compare them, e.g. `[1, 10, 0]` for `1.10.0`
+///
+/// Pre-release and build suffixes are ignored.
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or(version)
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// The version starts with the parts of the requirement, e.g. `1.2` matches `1.2.3` but not `1.20.0`
+fn matches_requirement(version: &str, requirement: &str) -> bool {
+    let mut parts = version.split('.');
+    requirement
+        .split('.')
+        .all(|required| parts.next() == Some(required))
+}
+
+/// Source directories of the local cargo registry
+fn registry_source_dirs() -> Vec<PathBuf> {
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+
+    cargo_home
+        .and_then(|home| fs::read_dir(home.join("registry").join("src")).ok())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(package: &str, version: Option<&str>) -> Dependency {
+        Dependency {
+            name: package.to_string(),
+            package: package.to_string(),
+            kind: DependencyKind::Normal,
+            version: version.map(str::to_string),
+            path: None,
+            features: Vec::new(),
+            optional: false,
+            workspace: false,
+        }
+    }
+
+    fn locked(name: &str, version: &str) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn newest_registry_version_is_compared_by_numbers() {
+        let root =
+            std::env::temp_dir().join(format!("cargo-diagram-registry-{}", std::process::id()));
+        let registry = root.join("registry");
+        for dir_name in ["foo-1.9.0", "foo-1.10.0", "foo-bar-2.0.0"] {
+            fs::create_dir_all(registry.join(dir_name)).unwrap();
+            fs::write(registry.join(dir_name).join("Cargo.toml"), "").unwrap();
+        }
+
+        let found = locate_dependency(&dependency("foo", None), &root, &[], &[registry]);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            found.and_then(|dir| dir.file_name().map(|name| name.to_os_string())),
+            Some("foo-1.10.0".into())
+        );
+    }
+
+    #[test]
+    fn locked_version_matches_whole_parts() {
+        let lock = [locked("foo", "10.0.0"), locked("foo", "1.2.3")];
+        let version = |requirement| locked_version(&dependency("foo", Some(requirement)), &lock);
+        assert_eq!(version("1").as_deref(), Some("1.2.3"));
+        assert_eq!(version("^1.2").as_deref(), Some("1.2.3"));
+        assert_eq!(version("10").as_deref(), Some("10.0.0"));
+        // `1.2` is not a prefix of `1.20.0`, the last locked version is taken
+        let lock = [locked("foo", "1.20.0"), locked("foo", "2.0.0")];
+        assert_eq!(
+            locked_version(&dependency("foo", Some("1.2")), &lock).as_deref(),
+            Some("2.0.0")
         );
     }
 }
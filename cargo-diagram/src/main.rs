@@ -1,15 +1,40 @@
 //! Creates diagrams about your crate
-use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use analyzer::analyze_repository;
+use analyzer::{
+    analyze_dependencies, analyze_expanded, analyze_repository, record_crate_dependencies,
+    record_workspace_crates, DependencyOptions,
+};
+use cargo_diagram_printers::c4::print_c4_to_file;
+use cargo_diagram_printers::call_graph::print_call_graph_to_file;
+use cargo_diagram_printers::conversions::print_conversions_to_file;
+use cargo_diagram_printers::crates::print_crates_to_file;
+use cargo_diagram_printers::d2::print_d2_to_file;
+use cargo_diagram_printers::dispatch::print_dispatch_report_to_file;
+use cargo_diagram_printers::dot::print_dot_to_file;
+use cargo_diagram_printers::errors::print_errors_to_file;
+use cargo_diagram_printers::features::print_features_to_file;
+use cargo_diagram_printers::flow::print_flow_to_file;
+use cargo_diagram_printers::mermaid::print_mermaid_to_file;
+use cargo_diagram_printers::module_graph::print_module_graph_to_file;
+use cargo_diagram_printers::proc_macros::print_proc_macro_report_to_file;
+use cargo_diagram_printers::sequence::print_sequence_to_file;
+use cargo_diagram_printers::state_machine::print_state_machine_to_file;
+use cargo_diagram_printers::structs::print_structs_to_file;
+use cargo_diagram_printers::trait_matrix::print_trait_matrix_to_file;
 use cargo_diagram_printers::uml::print_uml_to_file;
-use cargo_diagram_printers::PrintOptions;
+use cargo_diagram_printers::{Format, PrintOptions, TableFormat};
+use cargo_diagram_visitors::call_graph::CallGraph;
 use cargo_diagram_visitors::module_visitor::ModulesVisitor;
+use cargo_diagram_visitors::rustdoc::analyze_rustdoc_json;
+use cargo_diagram_visitors::state_machine::StateMachine;
+use clap::Parser;
 use clap::Subcommand;
-use clap::{command, Parser};
+use clap::ValueEnum;
 
 mod analyzer;
+mod manifest;
+mod toml;
 
 #[derive(Parser)]
 #[command(name = "cargo-diagram", about = "A tool for generating diagrams")]
@@ -28,29 +53,272 @@ enum Commands {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct DiagramArgs {
+    #[command(subcommand)]
+    kind: Option<DiagramKind>,
+
     /// Show relations inside of the diagram (alpha)
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     relations: bool,
 
+    /// Show dependencies to the types used in function signatures as dashed edges
+    #[arg(short, long, default_value_t = false, global = true)]
+    signature_edges: bool,
+
     /// Select a different path to search
-    #[arg(short, long, default_value = "./")]
+    #[arg(short, long, default_value = "./", global = true)]
     path: PathBuf,
 
-    /// Select a different path to search
-    #[arg(short, long, default_value = "./overview.puml")]
-    output: PathBuf,
+    /// Output file, defaults to a file named after the diagram
+    #[arg(short, long, global = true)]
+    output: Option<PathBuf>,
+
+    /// Output format, the default and the supported formats depend on the diagram
+    #[arg(long, value_enum, global = true)]
+    format: Option<OutputFormat>,
 
     // The color of a module (plantuml colors)
-    #[arg(short, long, default_value = "#lightskyblue")]
+    #[arg(short, long, default_value = "#lightskyblue", global = true)]
     module_color: String,
 
     // The color of a trait (plantuml colors)
-    #[arg(short, long, default_value = "#violet")]
+    #[arg(short, long, default_value = "#violet", global = true)]
     trait_color: String,
 
     /// Draw private functions
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     functions_private: bool,
+
+    /// Draw "uses" edges from modules to the macros they invoke
+    #[arg(long, default_value_t = false, global = true)]
+    macro_edges: bool,
+
+    /// Draw the used proc macros with edges from the annotated items
+    #[arg(long, default_value_t = false, global = true)]
+    proc_macro_edges: bool,
+
+    /// Draw implemented traits outside of the diagram as stub interfaces instead of text
+    #[arg(long, default_value_t = false, global = true)]
+    external_trait_stubs: bool,
+
+    /// Parse every file again instead of reusing cached results from target/
+    #[arg(long, default_value_t = false, global = true)]
+    no_cache: bool,
+
+    /// Analyze dependency crates from local sources (registry, vendor/, path)
+    #[arg(long, default_value_t = false, global = true)]
+    dependencies: bool,
+
+    /// Levels of dependencies to analyze, 1 for direct dependencies only
+    #[arg(long, default_value_t = 1, global = true)]
+    dependency_depth: usize,
+
+    /// Only analyze these dependency crates (comma separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    dependency_allow: Vec<String>,
+
+    /// Draw the content of dependency crates instead of collapsed packages
+    #[arg(long, default_value_t = false, global = true)]
+    expand_dependencies: bool,
+
+    /// Read the crates from rustdoc JSON files instead of the sources
+    #[arg(long, global = true)]
+    rustdoc_json: Vec<PathBuf>,
+
+    /// Read a crate from a macro expanded file (CRATE=FILE), see `-Zunpretty=expanded`
+    #[arg(long, value_parser = parse_expanded, global = true)]
+    expanded: Vec<(String, PathBuf)>,
+}
+
+/// Kind of the generated diagram
+#[derive(Subcommand, Debug, Clone)]
+enum DiagramKind {
+    /// Modules with their structs, traits and functions (default)
+    Overview,
+    /// Class diagram of the structs, enums and traits without packages
+    Structs,
+    /// Dependencies between the modules from `use` statements and paths
+    ModuleGraph,
+    /// Markdown report of the derive and attribute macros grouped by macro crate
+    ProcMacros,
+    /// Calls between the functions, resolved by name
+    CallGraph(CallGraphArgs),
+    /// Sequence diagram of the calls starting at a function
+    Sequence(SequenceArgs),
+    /// Activity diagram of the control flow of a function
+    Flow(FlowArgs),
+    /// State diagram of an enum from the `match` expressions on its variants
+    StateMachine(StateMachineArgs),
+    /// C4 component diagram with crates as containers and top level modules as components
+    C4(C4Args),
+    /// Workspace crates with their dependencies from the Cargo manifests and `Cargo.lock`
+    Crates,
+    /// Cargo features of the workspace crates with the features and dependencies they enable
+    Features(FeaturesArgs),
+    /// Table of the traits implemented by the structs and enums
+    TraitMatrix,
+    /// Markdown report of the `dyn Trait` and `impl Trait` usages grouped by trait
+    Dispatch,
+    /// Conversions between types from their `From`, `TryFrom`, `Into` and `AsRef` impls
+    Conversions,
+    /// Error types with their messages, wrapped sources and `From` conversions
+    Errors,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct FeaturesArgs {
+    /// Show the modules and items gated by each feature with `#[cfg(feature = "..")]`
+    #[arg(long)]
+    items: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct C4Args {
+    /// Crates drawn as external systems (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    external: Vec<String>,
+
+    /// Crates drawn as databases (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    database: Vec<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct StateMachineArgs {
+    /// The enum, e.g. `crate::module::State` or `State`
+    #[arg(long = "enum")]
+    enum_path: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct FlowArgs {
+    /// The function, e.g. `crate::module::Type::method` or `Type::method`
+    #[arg(long)]
+    function: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct SequenceArgs {
+    /// Start function, e.g. `crate::module::Type::method` or `Type::method`
+    #[arg(long)]
+    function: String,
+
+    /// Maximum number of calls to follow from the start function
+    #[arg(long)]
+    depth: Option<usize>,
+}
+
+/// Language of the diagram or format of the table
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Plantuml,
+    Mermaid,
+    Markdown,
+    Csv,
+    Html,
+    Dot,
+    D2,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Plantuml => "puml",
+            OutputFormat::Mermaid => "mmd",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Html => "html",
+            OutputFormat::Dot => "dot",
+            OutputFormat::D2 => "d2",
+        }
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+struct CallGraphArgs {
+    /// Start function, e.g. `crate::module::Type::method` or `Type::method`
+    #[arg(long)]
+    function: Option<String>,
+
+    /// Maximum number of calls to follow from the start function, requires `--function`
+    #[arg(long, requires = "function")]
+    depth: Option<usize>,
+}
+
+impl DiagramKind {
+    /// Name of the default output file without extension
+    fn name(&self) -> &'static str {
+        match self {
+            DiagramKind::Overview => "overview",
+            DiagramKind::Structs => "structs",
+            DiagramKind::ModuleGraph => "module_graph",
+            DiagramKind::ProcMacros => "proc_macros",
+            DiagramKind::CallGraph(_) => "call_graph",
+            DiagramKind::Sequence(_) => "sequence",
+            DiagramKind::Flow(_) => "flow",
+            DiagramKind::StateMachine(_) => "state_machine",
+            DiagramKind::C4(_) => "c4",
+            DiagramKind::Crates => "crates",
+            DiagramKind::Features(_) => "features",
+            DiagramKind::TraitMatrix => "trait_matrix",
+            DiagramKind::Dispatch => "dispatch",
+            DiagramKind::Conversions => "conversions",
+            DiagramKind::Errors => "errors",
+        }
+    }
+
+    /// Supported output formats, the first one is the default
+    fn formats(&self) -> &'static [OutputFormat] {
+        match self {
+            DiagramKind::Overview => &[
+                OutputFormat::Plantuml,
+                OutputFormat::Mermaid,
+                OutputFormat::Dot,
+                OutputFormat::D2,
+            ],
+            DiagramKind::Sequence(_) => &[OutputFormat::Plantuml, OutputFormat::Mermaid],
+            DiagramKind::TraitMatrix => &[
+                OutputFormat::Markdown,
+                OutputFormat::Csv,
+                OutputFormat::Plantuml,
+                OutputFormat::Html,
+            ],
+            DiagramKind::ProcMacros | DiagramKind::Dispatch => &[OutputFormat::Markdown],
+            _ => &[OutputFormat::Plantuml],
+        }
+    }
+
+    fn default_output(&self, format: OutputFormat) -> PathBuf {
+        PathBuf::from(format!("./{}.{}", self.name(), format.extension()))
+    }
+}
+
+/// Exit if the function selected with `--function` does not exist
+fn check_function(visitor: &ModulesVisitor, options: &PrintOptions) {
+    if let Some(function) = &options.function {
+        let graph = CallGraph::build(visitor, options.expand_dependencies);
+        if graph.find(visitor, function).is_empty() {
+            eprintln!("Function '{}' not found", function);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Exit if the enum selected with `--enum` does not exist
+fn check_enum(visitor: &ModulesVisitor, options: &PrintOptions) {
+    if let Some(enum_path) = &options.state_enum {
+        if StateMachine::find_enum(visitor, enum_path).is_none() {
+            eprintln!("Enum '{}' not found", enum_path);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parse `CRATE=FILE`
+fn parse_expanded(value: &str) -> Result<(String, PathBuf), String> {
+    value
+        .split_once('=')
+        .map(|(crate_name, file)| (crate_name.to_string(), PathBuf::from(file)))
+        .ok_or_else(|| format!("expected CRATE=FILE, got '{}'", value))
 }
 
 fn main() {
@@ -61,21 +329,139 @@ fn main() {
         Commands::Diagram(args) => {
             let project_root = &args.path;
 
-            let mut visitor = ModulesVisitor {
-                module_map: BTreeMap::new(),
-                current_module: String::new(),
-            };
+            let mut visitor = ModulesVisitor::default();
+            let cache_dir = project_root.join("target").join("cargo-diagram");
 
+            let kind = args.kind.unwrap_or(DiagramKind::Overview);
+            let (function, depth) = match &kind {
+                DiagramKind::CallGraph(call_args) => (call_args.function.clone(), call_args.depth),
+                DiagramKind::Sequence(sequence_args) => {
+                    (Some(sequence_args.function.clone()), sequence_args.depth)
+                }
+                DiagramKind::Flow(flow_args) => (Some(flow_args.function.clone()), None),
+                _ => (None, None),
+            };
+            let (external, databases) = match &kind {
+                DiagramKind::C4(c4_args) => (c4_args.external.clone(), c4_args.database.clone()),
+                _ => (Vec::new(), Vec::new()),
+            };
+            let state_enum = match &kind {
+                DiagramKind::StateMachine(state_args) => Some(state_args.enum_path.clone()),
+                _ => None,
+            };
+            let feature_items =
+                matches!(&kind, DiagramKind::Features(features_args) if features_args.items);
+            let output_format = args.format.unwrap_or(kind.formats()[0]);
+            if !kind.formats().contains(&output_format) {
+                let name = output_format
+                    .to_possible_value()
+                    .expect("no skipped formats");
+                eprintln!(
+                    "The {} diagram does not support the {} format",
+                    kind.name(),
+                    name.get_name()
+                );
+                std::process::exit(1);
+            }
+            let format = match output_format {
+                OutputFormat::Mermaid => Format::Mermaid,
+                _ => Format::PlantUml,
+            };
+            let table_format = match output_format {
+                OutputFormat::Csv => TableFormat::Csv,
+                OutputFormat::Plantuml => TableFormat::PlantUml,
+                OutputFormat::Html => TableFormat::Html,
+                _ => TableFormat::Markdown,
+            };
             let options = PrintOptions {
                 relations: args.relations,
+                signature_edges: args.signature_edges,
                 module_color: args.module_color,
                 trait_color: args.trait_color,
                 functions_private: args.functions_private,
+                expand_dependencies: args.expand_dependencies,
+                macro_edges: args.macro_edges,
+                external_trait_stubs: args.external_trait_stubs,
+                proc_macros: args.proc_macro_edges,
+                function,
+                depth,
+                state_enum,
+                external,
+                databases,
+                feature_items,
+                format,
+                table_format,
             };
 
-            analyze_repository(project_root, &mut visitor);
+            let cache_dir = (!args.no_cache).then_some(cache_dir.as_path());
+            if args.rustdoc_json.is_empty() {
+                analyze_repository(project_root, &mut visitor, cache_dir);
+            } else {
+                for json_file in &args.rustdoc_json {
+                    analyze_rustdoc_json(json_file, &mut visitor);
+                }
+            }
+
+            for (crate_name, expanded_file) in &args.expanded {
+                analyze_expanded(project_root, crate_name, expanded_file, &mut visitor);
+            }
+
+            if args.dependencies {
+                let dependency_options = DependencyOptions {
+                    depth: args.dependency_depth,
+                    allow: args.dependency_allow,
+                };
+                analyze_dependencies(project_root, &mut visitor, cache_dir, &dependency_options);
+            }
 
-            print_uml_to_file(&visitor, &PathBuf::from(args.output), options);
+            let output = args
+                .output
+                .unwrap_or_else(|| kind.default_output(output_format));
+            match kind {
+                DiagramKind::Overview => match output_format {
+                    OutputFormat::Mermaid => print_mermaid_to_file(&visitor, &output, options),
+                    OutputFormat::Dot => print_dot_to_file(&visitor, &output, options),
+                    OutputFormat::D2 => print_d2_to_file(&visitor, &output, options),
+                    _ => print_uml_to_file(&visitor, &output, options),
+                },
+                DiagramKind::Structs => print_structs_to_file(&visitor, &output, options),
+                DiagramKind::ModuleGraph => print_module_graph_to_file(&visitor, &output, options),
+                DiagramKind::ProcMacros => {
+                    print_proc_macro_report_to_file(&visitor, &output, options)
+                }
+                DiagramKind::CallGraph(_) => {
+                    check_function(&visitor, &options);
+                    print_call_graph_to_file(&visitor, &output, options)
+                }
+                DiagramKind::Sequence(_) => {
+                    check_function(&visitor, &options);
+                    print_sequence_to_file(&visitor, &output, options)
+                }
+                DiagramKind::Flow(_) => {
+                    check_function(&visitor, &options);
+                    print_flow_to_file(&visitor, &output, options)
+                }
+                DiagramKind::StateMachine(_) => {
+                    check_enum(&visitor, &options);
+                    print_state_machine_to_file(&visitor, &output, options)
+                }
+                DiagramKind::C4(_) => {
+                    record_crate_dependencies(project_root, &mut visitor);
+                    print_c4_to_file(&visitor, &output, options)
+                }
+                DiagramKind::Crates => {
+                    record_workspace_crates(project_root, &mut visitor);
+                    print_crates_to_file(&visitor, &output, options)
+                }
+                DiagramKind::Features(_) => {
+                    record_workspace_crates(project_root, &mut visitor);
+                    print_features_to_file(&visitor, &output, options)
+                }
+                DiagramKind::TraitMatrix => print_trait_matrix_to_file(&visitor, &output, options),
+                DiagramKind::Dispatch => print_dispatch_report_to_file(&visitor, &output, options),
+                DiagramKind::Conversions => print_conversions_to_file(&visitor, &output, options),
+                DiagramKind::Errors => print_errors_to_file(&visitor, &output, options),
+            }
         }
     }
 }
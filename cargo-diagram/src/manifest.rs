@@ -0,0 +1,222 @@
+//! Read Cargo manifests and lock files
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::toml::{self, Table, Value};
+
+pub use cargo_diagram_visitors::crates::DependencyKind;
+
+/// A crate manifest (Cargo.toml)
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub lib_path: Option<PathBuf>,
+    pub dependencies: Vec<Dependency>,
+    /// Dependencies shared through `[workspace.dependencies]`
+    pub workspace_dependencies: Vec<Dependency>,
+    /// Version shared through `[workspace.package]`
+    pub workspace_version: Option<String>,
+    /// Features with the features and dependencies they enable
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// Name used inside of the dependent crate
+    pub name: String,
+    /// Name of the package on the registry
+    pub package: String,
+    pub kind: DependencyKind,
+    pub version: Option<String>,
+    pub path: Option<PathBuf>,
+    pub features: Vec<String>,
+    pub optional: bool,
+    /// Inherited from `[workspace.dependencies]`
+    pub workspace: bool,
+}
+
+/// A package of a lock file (Cargo.lock)
+#[derive(Debug, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Dependencies as `name` or, if several versions are locked, `name version`
+    pub dependencies: Vec<String>,
+}
+
+impl Manifest {
+    /// Read the manifest of a crate directory
+    pub fn load(dir: &Path) -> Option<Manifest> {
+        let table = read_toml(&dir.join("Cargo.toml"))?;
+        let package = table.get("package");
+
+        let mut dependencies = Vec::new();
+        collect_dependencies(&table, dir, &mut dependencies);
+        // Platform specific dependencies: [target.'cfg(..)'.dependencies]
+        if let Some(targets) = table.get("target").and_then(Value::as_table) {
+            for target in targets.values().filter_map(Value::as_table) {
+                collect_dependencies(target, dir, &mut dependencies);
+            }
+        }
+
+        let mut workspace_dependencies = Vec::new();
+        if let Some(deps) = table
+            .get("workspace")
+            .and_then(|workspace| workspace.get("dependencies"))
+            .and_then(Value::as_table)
+        {
+            for (name, value) in deps {
+                workspace_dependencies.push(parse_dependency(
+                    name,
+                    value,
+                    DependencyKind::Normal,
+                    dir,
+                ));
+            }
+        }
+
+        Some(Manifest {
+            dir: dir.to_path_buf(),
+            name: package
+                .and_then(|package| package.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            version: package
+                .and_then(|package| package.get("version"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            lib_path: table
+                .get("lib")
+                .and_then(|lib| lib.get("path"))
+                .and_then(Value::as_str)
+                .map(|path| dir.join(path)),
+            dependencies,
+            workspace_dependencies,
+            workspace_version: table
+                .get("workspace")
+                .and_then(|workspace| workspace.get("package"))
+                .and_then(|package| package.get("version"))
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            features: table
+                .get("features")
+                .and_then(Value::as_table)
+                .map(|features| {
+                    features
+                        .iter()
+                        .map(|(name, enabled)| (name.clone(), enabled.strings()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Name of the crate as used in rust code
+    pub fn crate_name(&self) -> Option<String> {
+        self.name.as_ref().map(|name| name.replace('-', "_"))
+    }
+
+    /// Fill dependencies declared with `workspace = true` from the workspace manifest
+    pub fn inherit_workspace(&mut self, workspace: &Manifest) {
+        if self.version.is_none() {
+            self.version = workspace.workspace_version.clone();
+        }
+        for dependency in self.dependencies.iter_mut().filter(|dep| dep.workspace) {
+            if let Some(shared) = workspace
+                .workspace_dependencies
+                .iter()
+                .find(|shared| shared.name == dependency.name)
+            {
+                dependency.package = shared.package.clone();
+                dependency.version = shared.version.clone();
+                dependency.path = shared.path.clone();
+                dependency.features.extend(shared.features.iter().cloned());
+            }
+        }
+    }
+}
+
+/// Read the packages of a lock file
+pub fn load_lock_file(path: &Path) -> Vec<LockedPackage> {
+    let Some(table) = read_toml(path) else {
+        return Vec::new();
+    };
+
+    table
+        .get("package")
+        .and_then(Value::as_array)
+        .map(|packages| {
+            packages
+                .iter()
+                .filter_map(|package| {
+                    Some(LockedPackage {
+                        name: package.get("name")?.as_str()?.to_string(),
+                        version: package.get("version")?.as_str()?.to_string(),
+                        dependencies: package
+                            .get("dependencies")
+                            .map(Value::strings)
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_toml(path: &Path) -> Option<Table> {
+    let content = fs::read_to_string(path).ok()?;
+    match toml::parse(&content) {
+        Ok(table) => Some(table),
+        Err(err) => {
+            debug!("Fehler beim Lesen von {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+fn collect_dependencies(table: &Table, dir: &Path, dependencies: &mut Vec<Dependency>) {
+    let sections = [
+        ("dependencies", DependencyKind::Normal),
+        ("dev-dependencies", DependencyKind::Dev),
+        ("build-dependencies", DependencyKind::Build),
+    ];
+
+    for (section, kind) in sections {
+        if let Some(deps) = table.get(section).and_then(Value::as_table) {
+            for (name, value) in deps {
+                dependencies.push(parse_dependency(name, value, kind, dir));
+            }
+        }
+    }
+}
+
+fn parse_dependency(name: &str, value: &Value, kind: DependencyKind, dir: &Path) -> Dependency {
+    let field = |key: &str| value.get(key).and_then(Value::as_str);
+
+    Dependency {
+        name: name.to_string(),
+        package: field("package").unwrap_or(name).to_string(),
+        kind,
+        version: value
+            .as_str()
+            .or_else(|| field("version"))
+            .map(str::to_string),
+        path: field("path").map(|path| dir.join(path)),
+        features: value
+            .get("features")
+            .map(Value::strings)
+            .unwrap_or_default(),
+        optional: value
+            .get("optional")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        workspace: value
+            .get("workspace")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    }
+}
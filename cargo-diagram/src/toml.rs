@@ -0,0 +1,553 @@
+//! Minimal TOML reader for Cargo manifests and lock files
+use std::collections::BTreeMap;
+
+pub type Table = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Boolean(bool),
+    /// Numbers and dates are kept as written
+    Other(String),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// Get a value of a table by key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_table().and_then(|table| table.get(key))
+    }
+
+    /// Collect the strings of an array
+    pub fn strings(&self) -> Vec<String> {
+        self.as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Parse a TOML document
+pub fn parse(input: &str) -> Result<Table, String> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    parser.document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn document(&mut self) -> Result<Table, String> {
+        let mut root = Table::new();
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_trivia(true);
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.pos += 1;
+                    let array = self.eat('[');
+                    let path = self.key()?;
+                    self.expect(']')?;
+                    if array {
+                        self.expect(']')?;
+                        push_array_table(&mut root, &path).map_err(|err| self.error(&err))?;
+                    } else {
+                        table_at(&mut root, &path).map_err(|err| self.error(&err))?;
+                    }
+                    current = path;
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.skip_whitespace();
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    let table = table_at(&mut root, &current).map_err(|err| self.error(&err))?;
+                    insert(table, &key, value).map_err(|err| self.error(&err))?;
+                }
+            }
+            self.skip_whitespace();
+            self.skip_comment();
+            if !matches!(self.peek(), None | Some('\n') | Some('\r')) {
+                return Err(self.error("expected end of line"));
+            }
+        }
+
+        Ok(root)
+    }
+
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_whitespace();
+            if !self.eat('.') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                loop {
+                    self.skip_trivia(true);
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_trivia(true);
+                    if !self.eat(',') {
+                        self.expect(']')?;
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut table = Table::new();
+                self.skip_whitespace();
+                if self.eat('}') {
+                    return Ok(Value::Table(table));
+                }
+                loop {
+                    let key = self.key()?;
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    insert(&mut table, &key, value).map_err(|err| self.error(&err))?;
+                    self.skip_whitespace();
+                    if !self.eat(',') {
+                        self.expect('}')?;
+                        return Ok(Value::Table(table));
+                    }
+                }
+            }
+            Some(_) => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r'))
+                {
+                    self.pos += 1;
+                }
+                let raw: String = self.chars[start..self.pos].iter().collect();
+                match raw.trim() {
+                    "" => Err(self.error("expected value")),
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    other => Ok(Value::Other(other.to_string())),
+                }
+            }
+            None => Err(self.error("expected value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        let multiline = self.starts_with("\"\"\"");
+        self.pos += if multiline { 3 } else { 1 };
+        if multiline {
+            self.eat_newline();
+        }
+
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') if !multiline => return Ok(value),
+                Some('"') if self.starts_with("\"\"") => {
+                    self.pos += 2;
+                    return Ok(value);
+                }
+                Some('\\') => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('u') => value.push(self.unicode(4)?),
+                    Some('U') => value.push(self.unicode(8)?),
+                    Some(c) if multiline && c.is_whitespace() => {
+                        // Line ending backslash trims the following whitespace
+                        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+                            self.pos += 1;
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some('\n') if !multiline => return Err(self.error("unterminated string")),
+                Some(c) => value.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        let multiline = self.starts_with("'''");
+        self.pos += if multiline { 3 } else { 1 };
+        if multiline {
+            self.eat_newline();
+        }
+
+        let terminator = if multiline { "'''" } else { "'" };
+        let start = self.pos;
+        while !self.starts_with(terminator) {
+            match self.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('\n') if !multiline => return Err(self.error("unterminated string")),
+                Some(_) => {}
+            }
+        }
+        let value = self.chars[start..self.pos].iter().collect();
+        self.pos += terminator.len();
+        Ok(value)
+    }
+
+    fn unicode(&mut self, len: usize) -> Result<char, String> {
+        let digits: String = self.chars.iter().skip(self.pos).take(len).collect();
+        self.pos += len;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_newline(&mut self) {
+        self.eat('\r');
+        self.eat('\n');
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip whitespace, comments and optionally line breaks
+    fn skip_trivia(&mut self, newlines: bool) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') if newlines => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count();
+        format!("line {}: {}", line + 1, message)
+    }
+}
+
+/// Get the table at a path, creating missing tables on the way
+fn table_at<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for key in path {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match entry {
+            Value::Table(table) => table,
+            // Arrays of tables continue in their last element
+            Value::Array(values) => match values.last_mut() {
+                Some(Value::Table(table)) => table,
+                _ => return Err(format!("'{}' is not a table", key)),
+            },
+            _ => return Err(format!("'{}' is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+fn push_array_table(root: &mut Table, path: &[String]) -> Result<(), String> {
+    let (last, parent) = path.split_last().ok_or("empty table name")?;
+    let table = table_at(root, parent)?;
+    match table
+        .entry(last.clone())
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(values) => {
+            values.push(Value::Table(Table::new()));
+            Ok(())
+        }
+        _ => Err(format!("'{}' is not an array", last)),
+    }
+}
+
+fn insert(table: &mut Table, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parent) = key.split_last().ok_or("empty key")?;
+    let table = table_at(table, parent)?;
+    if table.insert(last.clone(), value).is_some() {
+        return Err(format!("duplicate key '{}'", last));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    #[test]
+    fn inline_tables() {
+        let table = parse(
+            r#"[dependencies]
+serde = { version = "1", features = ["derive"], optional = true }
+empty = {}
+"#,
+        )
+        .unwrap();
+
+        let serde = &table["dependencies"].get("serde").unwrap();
+        assert_eq!(serde.get("version"), Some(&string("1")));
+        assert_eq!(serde.get("features").unwrap().strings(), ["derive"]);
+        assert_eq!(serde.get("optional").and_then(Value::as_bool), Some(true));
+        assert_eq!(
+            table["dependencies"].get("empty"),
+            Some(&Value::Table(Table::new()))
+        );
+    }
+
+    #[test]
+    fn dotted_keys() {
+        let table = parse(
+            r#"package.name = "app"
+[dependencies]
+tokio.version = "1"
+tokio.features = ["rt"]
+"quoted.key" = 'literal'
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(table["package"].get("name"), Some(&string("app")));
+        let tokio = table["dependencies"].get("tokio").unwrap();
+        assert_eq!(tokio.get("version"), Some(&string("1")));
+        assert_eq!(tokio.get("features").unwrap().strings(), ["rt"]);
+        assert_eq!(
+            table["dependencies"].get("quoted.key"),
+            Some(&string("literal"))
+        );
+    }
+
+    #[test]
+    fn workspace_dependencies() {
+        let table = parse(
+            r#"[workspace]
+members = ["app", "lib"]
+
+[workspace.dependencies]
+serde = "1" # inline comment
+lib = { path = "lib" }
+"#,
+        )
+        .unwrap();
+
+        let workspace = &table["workspace"];
+        assert_eq!(workspace.get("members").unwrap().strings(), ["app", "lib"]);
+        let dependencies = workspace.get("dependencies").unwrap();
+        assert_eq!(dependencies.get("serde"), Some(&string("1")));
+        assert_eq!(
+            dependencies.get("lib").and_then(|lib| lib.get("path")),
+            Some(&string("lib"))
+        );
+    }
+
+    #[test]
+    fn arrays_of_tables_in_lock_files() {
+        let table = parse(
+            r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "serde",
+ "syn 2.0.0",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(table["version"], Value::Other("3".to_string()));
+        let packages = table["package"].as_array().unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].get("name"), Some(&string("app")));
+        assert_eq!(
+            packages[0].get("dependencies").unwrap().strings(),
+            ["serde", "syn 2.0.0"]
+        );
+        assert_eq!(packages[1].get("name"), Some(&string("serde")));
+        assert!(packages[1].get("dependencies").is_none());
+    }
+
+    #[test]
+    fn multi_line_arrays() {
+        let table = parse(
+            r#"features = [
+    # comment inside of the array
+    "a",
+    [1, 2],
+
+    "b", # trailing comment
+]
+"#,
+        )
+        .unwrap();
+
+        let features = table["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[0], string("a"));
+        assert_eq!(
+            features[1],
+            Value::Array(vec![
+                Value::Other("1".to_string()),
+                Value::Other("2".to_string())
+            ])
+        );
+        assert_eq!(features[2], string("b"));
+    }
+
+    #[test]
+    fn escapes() {
+        let table = parse(
+            r#"basic = "tab\tquote\"backslash\\newline\n"
+unicode = "\u00e9\U0001F600"
+literal = 'C:\path\no\escapes'
+multi = """
+first \
+    second"""
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(table["basic"], string("tab\tquote\"backslash\\newline\n"));
+        assert_eq!(table["unicode"], string("é😀"));
+        assert_eq!(table["literal"], string("C:\\path\\no\\escapes"));
+        assert_eq!(table["multi"], string("first second"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("name = \"unterminated").is_err());
+        assert!(parse("name = \"\\q\"").is_err());
+        assert!(parse("name = \"\\u12\"").is_err());
+        assert!(parse("a = 1\na = 2").is_err());
+        assert!(parse("[package").is_err());
+        assert!(parse("name =").is_err());
+        assert!(parse("a = 1\n[a]").is_err());
+    }
+}
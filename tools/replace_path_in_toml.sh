@@ -6,46 +6,36 @@ if [ -z "$1" ]; then
   exit 1
 fi
 
-# The version to replace the path with (only used when setting version)
+# The version of the workspace crates to depend on (only used when setting version)
 VERSION=$1
 
-# Function to update dependencies to use version
-update_to_version() {
-  local file="$1"
-  sed -i.bak -E "s|path *= *\"[^\"]+\"|version = \"$VERSION\"|g" "$file"
-  echo "Updated $file to use version $VERSION"
-}
-
-# Function to update dependencies to use path
-update_to_path() {
-  local file="$1"
-  
-  # Read the package name from the file to construct the path
-  local package_name
-  package_name=$(grep '^name = "' "$file" | sed -E 's/name = "(.*)"/\1/')
-
-  # Construct the path based on the package name
-  local path="../$package_name"
+# Workspace crates that other workspace crates depend on
+CRATES="cargo-diagram-visitors cargo-diagram-printers"
 
-  # Update the version to path
-  sed -i.bak -E "s|version *= *\"[0-9]+\.[0-9]+\.[0-9]+\"|path = \"$path\"|g" "$file"
-  echo "Updated $file to use path $path"
+# Version of a workspace crate from its own manifest
+crate_version() {
+  grep -m1 '^version = "' "./$1/Cargo.toml" | sed -E 's/version = "(.*)"/\1/'
 }
 
-# Find all Cargo.toml files and process each one
-find . -name "Cargo.toml" | while read -r file; do
-  if grep -q 'path *= *"' "$file"; then
-    # If path exists, replace with version
-    update_to_version "$file"
-  elif grep -q 'version *= *"' "$file"; then
-    # If version exists, replace with path
-    update_to_path "$file"
+# Dependency spec of a workspace crate, the whole `{ .. }` table is replaced
+dependency_spec() {
+  local crate="$1"
+  if [ "$VERSION" = "reset" ]; then
+    echo "{ path = \"../$crate\", version = \"$(crate_version "$crate")\" }"
   else
-    echo "No path or version found in $file"
+    echo "{ version = \"$VERSION\" }"
   fi
+}
 
-  # Optionally, remove the backup files created by sed
-  rm "$file.bak"
+# Find all Cargo.toml files and process each one
+find . -name "Cargo.toml" -not -path "./target/*" | while read -r file; do
+  for crate in $CRATES; do
+    sed -i.bak -E "s|^$crate *= *\{[^}]*\}|$crate = $(dependency_spec "$crate")|" "$file"
+
+    # Remove the backup files created by sed
+    rm "$file.bak"
+  done
+  echo "Updated $file"
 done
 
 echo "Replacement complete."